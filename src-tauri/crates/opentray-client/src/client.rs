@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+use crate::models::{
+    Config, FileDiff, Health, McpStatus, MessageWithParts, PathInfo, PermissionReply,
+    Project, ProviderList, Session, TodoItem,
+};
+
+/// Header OpenCode uses to scope requests to a project directory
+pub const DIRECTORY_HEADER: &str = "x-opencode-directory";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent or the body could not be read
+    Http(reqwest::Error),
+    /// The server answered with a non-success status
+    Status { status: StatusCode, body: String },
+    /// The body did not match the expected model
+    Decode(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "request failed: {}", e),
+            Error::Status { status, body } => write!(f, "server returned {}: {}", status, body),
+            Error::Decode(e) => write!(f, "unexpected response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Client for one OpenCode server
+#[derive(Clone, Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    directory: Option<String>,
+}
+
+impl Client {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self::with_http(http, base_url)
+    }
+
+    /// Build a client around an existing `reqwest::Client`
+    pub fn with_http(http: reqwest::Client, base_url: impl Into<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            directory: None,
+        }
+    }
+
+    /// Scope every request to a project directory via `x-opencode-directory`
+    pub fn directory(mut self, directory: impl Into<String>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // --------------------------------------------
+    // Server
+    // --------------------------------------------
+
+    pub async fn health(&self) -> Result<Health> {
+        self.get("/global/health").await
+    }
+
+    pub async fn path(&self) -> Result<PathInfo> {
+        self.get("/path").await
+    }
+
+    pub async fn current_project(&self) -> Result<Project> {
+        self.get("/project/current").await
+    }
+
+    // --------------------------------------------
+    // Sessions
+    // --------------------------------------------
+
+    pub async fn sessions(&self) -> Result<Vec<Session>> {
+        self.get("/session").await
+    }
+
+    pub async fn session(&self, id: &str) -> Result<Session> {
+        self.get(&format!("/session/{}", id)).await
+    }
+
+    pub async fn create_session(&self) -> Result<Session> {
+        self.send(self.request(Method::POST, "/session").json(&json!({})))
+            .await
+    }
+
+    pub async fn delete_session(&self, id: &str) -> Result<()> {
+        self.send_empty(self.request(Method::DELETE, &format!("/session/{}", id)))
+            .await
+    }
+
+    pub async fn messages(&self, session_id: &str) -> Result<Vec<MessageWithParts>> {
+        self.get(&format!("/session/{}/message", session_id)).await
+    }
+
+    /// Send a text prompt to a session
+    pub async fn send_message(&self, session_id: &str, text: &str) -> Result<()> {
+        let body = json!({ "parts": [{ "type": "text", "text": text }] });
+        self.send_empty(
+            self.request(Method::POST, &format!("/session/{}/message", session_id))
+                .json(&body),
+        )
+        .await
+    }
+
+    pub async fn abort(&self, session_id: &str) -> Result<()> {
+        self.send_empty(self.request(Method::POST, &format!("/session/{}/abort", session_id)))
+            .await
+    }
+
+    pub async fn todos(&self, session_id: &str) -> Result<Vec<TodoItem>> {
+        self.get(&format!("/session/{}/todo", session_id)).await
+    }
+
+    pub async fn diffs(&self, session_id: &str) -> Result<Vec<FileDiff>> {
+        self.get(&format!("/session/{}/diff", session_id)).await
+    }
+
+    // --------------------------------------------
+    // Permissions
+    // --------------------------------------------
+
+    pub async fn reply_permission(&self, request_id: &str, reply: PermissionReply) -> Result<()> {
+        self.send_empty(
+            self.request(Method::POST, &format!("/permission/{}/reply", request_id))
+                .json(&json!({ "reply": reply })),
+        )
+        .await
+    }
+
+    // --------------------------------------------
+    // Config, Providers, MCP
+    // --------------------------------------------
+
+    pub async fn config(&self) -> Result<Config> {
+        self.get("/config").await
+    }
+
+    pub async fn update_config(&self, config: &serde_json::Value) -> Result<Config> {
+        self.send(self.request(Method::PATCH, "/config").json(config))
+            .await
+    }
+
+    pub async fn providers(&self) -> Result<ProviderList> {
+        self.get("/provider").await
+    }
+
+    /// MCP servers keyed by name, empty when none are configured
+    pub async fn mcp(&self) -> Result<HashMap<String, McpStatus>> {
+        self.get("/mcp").await
+    }
+
+    // --------------------------------------------
+    // Helpers
+    // --------------------------------------------
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut builder = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+        if let Some(directory) = &self.directory {
+            builder = builder.header(DIRECTORY_HEADER, directory);
+        }
+        builder
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.send(self.request(Method::GET, path)).await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, builder: RequestBuilder) -> Result<T> {
+        let response = check(builder.send().await?).await?;
+        let bytes = response.bytes().await?;
+        serde_json::from_slice(&bytes).map_err(Error::Decode)
+    }
+
+    async fn send_empty(&self, builder: RequestBuilder) -> Result<()> {
+        check(builder.send().await?).await?;
+        Ok(())
+    }
+}
+
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::Status { status, body })
+}
@@ -0,0 +1,7 @@
+//! Typed client for the OpenCode HTTP API used by OpenTray
+
+mod client;
+pub mod models;
+
+pub use client::{Client, Error, Result, DIRECTORY_HEADER};
+pub use models::*;
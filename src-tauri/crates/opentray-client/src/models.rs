@@ -0,0 +1,581 @@
+//! Serde models for the OpenCode HTTP API
+//! These mirror the TypeScript types in src/lib/types.ts
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// --------------------------------------------
+// Server
+// --------------------------------------------
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Health {
+    pub healthy: bool,
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PathInfo {
+    pub home: String,
+    pub state: String,
+    pub config: String,
+    pub worktree: String,
+    pub directory: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Project {
+    pub id: String,
+    #[serde(alias = "worktree")]
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+// --------------------------------------------
+// Sessions
+// --------------------------------------------
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Session {
+    pub id: String,
+    #[serde(rename = "projectID")]
+    pub project_id: String,
+    pub directory: String,
+    pub title: String,
+    pub time: SessionTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<SessionSummary>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SessionTime {
+    pub created: u64,
+    pub updated: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SessionSummary {
+    pub additions: u64,
+    pub deletions: u64,
+    pub files: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub messages: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct FileDiff {
+    pub file: String,
+    pub before: String,
+    pub after: String,
+    pub additions: u64,
+    pub deletions: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    Pending,
+    InProgress,
+    Completed,
+    Cancelled,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TodoPriority {
+    High,
+    Medium,
+    Low,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TodoItem {
+    pub id: String,
+    pub content: String,
+    pub status: TodoStatus,
+    pub priority: TodoPriority,
+}
+
+// --------------------------------------------
+// Messages
+// --------------------------------------------
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct MessageWithParts {
+    pub info: Message,
+    pub parts: Vec<Part>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "role", rename_all = "lowercase")]
+pub enum Message {
+    User(UserMessage),
+    Assistant(AssistantMessage),
+}
+
+impl Message {
+    pub fn id(&self) -> &str {
+        match self {
+            Message::User(m) => &m.id,
+            Message::Assistant(m) => &m.id,
+        }
+    }
+
+    pub fn session_id(&self) -> &str {
+        match self {
+            Message::User(m) => &m.session_id,
+            Message::Assistant(m) => &m.session_id,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ModelRef {
+    #[serde(rename = "providerID")]
+    pub provider_id: String,
+    #[serde(rename = "modelID")]
+    pub model_id: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct UserMessage {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    pub time: MessageTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<UserMessageSummary>,
+    pub agent: String,
+    pub model: ModelRef,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<HashMap<String, bool>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct UserMessageSummary {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub diffs: Vec<FileDiff>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct MessageTime {
+    pub created: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AssistantMessage {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    pub time: MessageTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<MessageError>,
+    #[serde(rename = "parentID")]
+    pub parent_id: String,
+    #[serde(rename = "modelID")]
+    pub model_id: String,
+    #[serde(rename = "providerID")]
+    pub provider_id: String,
+    pub mode: String,
+    pub agent: String,
+    pub path: MessagePath,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<bool>,
+    pub cost: f64,
+    pub tokens: Tokens,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct MessagePath {
+    pub cwd: String,
+    pub root: String,
+}
+
+/// OpenCode sends errors as `{ type, message }`, keep unknown shapes intact
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct MessageError {
+    #[serde(rename = "type", alias = "name")]
+    pub kind: String,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Tokens {
+    pub input: u64,
+    pub output: u64,
+    pub reasoning: u64,
+    pub cache: TokenCache,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TokenCache {
+    pub read: u64,
+    pub write: u64,
+}
+
+// --------------------------------------------
+// Parts
+// --------------------------------------------
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PartTime {
+    pub start: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<u64>,
+}
+
+/// A message part, tagged by `type`
+/// Part types this crate doesn't know about deserialize as `Unknown`
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Part {
+    Text(TextPart),
+    Reasoning(ReasoningPart),
+    Tool(ToolPart),
+    File(FilePart),
+    StepStart(StepStartPart),
+    StepFinish(StepFinishPart),
+    Agent(AgentPart),
+    Snapshot(SnapshotPart),
+    Patch(PatchPart),
+    Retry(RetryPart),
+    Compaction(CompactionPart),
+    Subtask(SubtaskPart),
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TextPart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synthetic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignored: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<PartTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, Value>>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ReasoningPart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, Value>>,
+    pub time: PartTime,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ToolPart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    #[serde(rename = "callID")]
+    pub call_id: String,
+    pub tool: String,
+    pub state: ToolState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, Value>>,
+}
+
+/// Tool execution state, tagged by `status`
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum ToolState {
+    Pending {
+        #[serde(default)]
+        input: HashMap<String, Value>,
+        #[serde(default)]
+        raw: String,
+    },
+    Running {
+        #[serde(default)]
+        input: HashMap<String, Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<HashMap<String, Value>>,
+        time: ToolTimeStart,
+    },
+    Completed {
+        #[serde(default)]
+        input: HashMap<String, Value>,
+        output: String,
+        title: String,
+        #[serde(default)]
+        metadata: HashMap<String, Value>,
+        time: ToolTime,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attachments: Option<Vec<FilePart>>,
+    },
+    Error {
+        #[serde(default)]
+        input: HashMap<String, Value>,
+        error: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<HashMap<String, Value>>,
+        time: ToolTime,
+    },
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ToolTimeStart {
+    pub start: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ToolTime {
+    pub start: u64,
+    pub end: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compacted: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct FilePart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    pub mime: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Value>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct StepStartPart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct StepFinishPart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+    pub cost: f64,
+    pub tokens: Tokens,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AgentPart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<AgentSource>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AgentSource {
+    pub value: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SnapshotPart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    pub snapshot: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PatchPart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    pub hash: String,
+    pub files: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct RetryPart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    pub attempt: u32,
+    pub error: RetryError,
+    pub time: RetryTime,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct RetryError {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct RetryTime {
+    pub created: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct CompactionPart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    pub auto: bool,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SubtaskPart {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    pub prompt: String,
+    pub description: String,
+    pub agent: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+// --------------------------------------------
+// Config, Providers, MCP
+// --------------------------------------------
+
+/// OpenCode config, keys this crate doesn't model are kept in `extra`
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
+pub struct Config {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub small_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_agent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<HashMap<String, Value>>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ProviderList {
+    #[serde(default)]
+    pub all: Vec<Provider>,
+    #[serde(default)]
+    pub default: HashMap<String, String>,
+    #[serde(default)]
+    pub connected: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Provider {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub env: Vec<String>,
+    #[serde(default)]
+    pub models: HashMap<String, Model>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Model {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "providerID", default)]
+    pub provider_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<ModelLimit>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ModelLimit {
+    pub context: u64,
+    pub output: u64,
+}
+
+/// Status of one MCP server as returned by `/mcp`, keyed by server name
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct McpStatus {
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub tools: Vec<String>,
+}
+
+impl McpStatus {
+    pub fn is_connected(&self) -> bool {
+        self.status.as_deref() == Some("connected")
+    }
+}
+
+/// Reply to a permission request
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionReply {
+    Once,
+    Always,
+    Reject,
+}
@@ -0,0 +1,238 @@
+use opentray_client::{Client, Error, Message, Part, PermissionReply, TodoStatus, ToolState};
+use serde_json::json;
+use wiremock::matchers::{body_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const DIRECTORY: &str = "/home/dev/project";
+
+fn client(server: &MockServer) -> Client {
+    Client::new(server.uri()).directory(DIRECTORY)
+}
+
+fn session_json(id: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "projectID": "proj_1",
+        "directory": DIRECTORY,
+        "title": "Fix the build",
+        "time": { "created": 1700000000000u64, "updated": 1700000005000u64 },
+        "summary": { "additions": 3, "deletions": 1, "files": 2 }
+    })
+}
+
+#[tokio::test]
+async fn sessions_send_directory_header() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/session"))
+        .and(header("x-opencode-directory", DIRECTORY))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([session_json("ses_1")])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let sessions = client(&server).sessions().await.unwrap();
+
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].id, "ses_1");
+    assert_eq!(sessions[0].project_id, "proj_1");
+    assert_eq!(sessions[0].summary.as_ref().unwrap().files, 2);
+}
+
+#[tokio::test]
+async fn messages_decode_parts_and_tool_state() {
+    let server = MockServer::start().await;
+    let body = json!([
+        {
+            "info": {
+                "id": "msg_1",
+                "sessionID": "ses_1",
+                "role": "user",
+                "time": { "created": 1 },
+                "agent": "build",
+                "model": { "providerID": "anthropic", "modelID": "claude" }
+            },
+            "parts": [
+                { "id": "prt_1", "sessionID": "ses_1", "messageID": "msg_1", "type": "text", "text": "hello" }
+            ]
+        },
+        {
+            "info": {
+                "id": "msg_2",
+                "sessionID": "ses_1",
+                "role": "assistant",
+                "time": { "created": 2, "completed": 3 },
+                "parentID": "msg_1",
+                "modelID": "claude",
+                "providerID": "anthropic",
+                "mode": "build",
+                "agent": "build",
+                "path": { "cwd": DIRECTORY, "root": DIRECTORY },
+                "cost": 0.01,
+                "tokens": { "input": 10, "output": 20, "reasoning": 0, "cache": { "read": 0, "write": 0 } },
+                "finish": "stop"
+            },
+            "parts": [
+                {
+                    "id": "prt_2", "sessionID": "ses_1", "messageID": "msg_2", "type": "tool",
+                    "callID": "call_1", "tool": "bash",
+                    "state": {
+                        "status": "completed",
+                        "input": { "command": "cargo test" },
+                        "output": "ok",
+                        "title": "cargo test",
+                        "metadata": {},
+                        "time": { "start": 2, "end": 3 }
+                    }
+                },
+                { "id": "prt_3", "sessionID": "ses_1", "messageID": "msg_2", "type": "step-start" },
+                { "id": "prt_4", "sessionID": "ses_1", "messageID": "msg_2", "type": "some-future-part", "value": 1 }
+            ]
+        }
+    ]);
+    Mock::given(method("GET"))
+        .and(path("/session/ses_1/message"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .mount(&server)
+        .await;
+
+    let messages = client(&server).messages("ses_1").await.unwrap();
+
+    assert_eq!(messages.len(), 2);
+    assert!(matches!(messages[0].info, Message::User(_)));
+    assert!(matches!(&messages[0].parts[0], Part::Text(p) if p.text == "hello"));
+
+    let Message::Assistant(assistant) = &messages[1].info else {
+        panic!("expected assistant message");
+    };
+    assert_eq!(assistant.tokens.output, 20);
+
+    let Part::Tool(tool) = &messages[1].parts[0] else {
+        panic!("expected tool part");
+    };
+    assert_eq!(tool.tool, "bash");
+    assert!(matches!(&tool.state, ToolState::Completed { output, .. } if output == "ok"));
+    assert!(matches!(messages[1].parts[1], Part::StepStart(_)));
+    assert_eq!(messages[1].parts[2], Part::Unknown);
+}
+
+#[tokio::test]
+async fn diffs_and_todos() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/session/ses_1/diff"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "file": "src/main.rs", "before": "a", "after": "b", "additions": 1, "deletions": 1 }
+        ])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/session/ses_1/todo"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            { "id": "1", "content": "Write tests", "status": "in_progress", "priority": "high" }
+        ])))
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    let diffs = client.diffs("ses_1").await.unwrap();
+    let todos = client.todos("ses_1").await.unwrap();
+
+    assert_eq!(diffs[0].file, "src/main.rs");
+    assert_eq!(todos[0].status, TodoStatus::InProgress);
+}
+
+#[tokio::test]
+async fn abort_and_permission_reply() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/session/ses_1/abort"))
+        .and(header("x-opencode-directory", DIRECTORY))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!(true)))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/permission/per_1/reply"))
+        .and(body_json(json!({ "reply": "always" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!(true)))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    client.abort("ses_1").await.unwrap();
+    client
+        .reply_permission("per_1", PermissionReply::Always)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn config_providers_and_mcp() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/config"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "model": "anthropic/claude",
+            "theme": "opencode"
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/provider"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "all": [{
+                "id": "anthropic",
+                "name": "Anthropic",
+                "env": ["ANTHROPIC_API_KEY"],
+                "models": { "claude": { "id": "claude", "name": "Claude", "providerID": "anthropic" } }
+            }],
+            "default": { "anthropic": "claude" }
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/mcp"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    let config = client.config().await.unwrap();
+    let providers = client.providers().await.unwrap();
+    let mcp = client.mcp().await.unwrap();
+
+    assert_eq!(config.model.as_deref(), Some("anthropic/claude"));
+    assert_eq!(config.extra["theme"], "opencode");
+    assert_eq!(providers.all[0].models["claude"].name, "Claude");
+    assert!(mcp.is_empty());
+}
+
+#[tokio::test]
+async fn error_status_is_reported() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/session/missing"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("not found"))
+        .mount(&server)
+        .await;
+
+    let err = client(&server).session("missing").await.unwrap_err();
+
+    assert!(matches!(err, Error::Status { status, ref body } if status == 404 && body == "not found"));
+}
+
+#[tokio::test]
+async fn model_drift_is_a_decode_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/session"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{ "id": "ses_1" }])))
+        .mount(&server)
+        .await;
+
+    let err = client(&server).sessions().await.unwrap_err();
+
+    assert!(matches!(err, Error::Decode(_)));
+}
@@ -0,0 +1,469 @@
+use tauri_plugin_global_shortcut::{Code, Modifiers, Shortcut};
+
+/// Why an accelerator string was rejected, serialized as `{ kind, message }`
+#[derive(serde::Serialize, Clone, PartialEq, Eq, Debug)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum AcceleratorError {
+    Empty(String),
+    /// A token that is neither a modifier nor a key, or an empty token (`Ctrl++O`)
+    UnknownToken(String),
+    DuplicateModifier(String),
+    MultipleKeys(String),
+    MissingKey(String),
+    /// Plain keys would swallow normal typing system-wide
+    MissingModifier(String),
+}
+
+impl std::fmt::Display for AcceleratorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AcceleratorError::Empty(msg)
+            | AcceleratorError::UnknownToken(msg)
+            | AcceleratorError::DuplicateModifier(msg)
+            | AcceleratorError::MultipleKeys(msg)
+            | AcceleratorError::MissingKey(msg)
+            | AcceleratorError::MissingModifier(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+// `Cmd` on macOS, `Super` elsewhere
+#[cfg(target_os = "macos")]
+const SUPER_NAME: &str = "Cmd";
+#[cfg(not(target_os = "macos"))]
+const SUPER_NAME: &str = "Super";
+
+// `CmdOrCtrl` resolves to the platform's primary modifier
+#[cfg(target_os = "macos")]
+const PRIMARY: Modifiers = Modifiers::SUPER;
+#[cfg(not(target_os = "macos"))]
+const PRIMARY: Modifiers = Modifiers::CONTROL;
+
+fn parse_modifier(token: &str) -> Option<Modifiers> {
+    match token.to_ascii_lowercase().as_str() {
+        "cmdorctrl" | "cmdorcontrol" | "commandorcontrol" | "commandorctrl" => Some(PRIMARY),
+        "cmd" | "command" | "meta" | "super" | "win" => Some(Modifiers::SUPER),
+        "ctrl" | "control" => Some(Modifiers::CONTROL),
+        "alt" | "option" => Some(Modifiers::ALT),
+        "shift" => Some(Modifiers::SHIFT),
+        _ => None,
+    }
+}
+
+/// Keys by canonical name (used by `format`) and accepted aliases
+///
+/// Canonical names follow `KeyboardEvent.code` with the `Key`/`Digit`/`Arrow`
+/// prefixes dropped and `Numpad` shortened to `Num`.
+const KEYS: &[(&str, Code, &[&str])] = &[
+    ("A", Code::KeyA, &["KeyA"]),
+    ("B", Code::KeyB, &["KeyB"]),
+    ("C", Code::KeyC, &["KeyC"]),
+    ("D", Code::KeyD, &["KeyD"]),
+    ("E", Code::KeyE, &["KeyE"]),
+    ("F", Code::KeyF, &["KeyF"]),
+    ("G", Code::KeyG, &["KeyG"]),
+    ("H", Code::KeyH, &["KeyH"]),
+    ("I", Code::KeyI, &["KeyI"]),
+    ("J", Code::KeyJ, &["KeyJ"]),
+    ("K", Code::KeyK, &["KeyK"]),
+    ("L", Code::KeyL, &["KeyL"]),
+    ("M", Code::KeyM, &["KeyM"]),
+    ("N", Code::KeyN, &["KeyN"]),
+    ("O", Code::KeyO, &["KeyO"]),
+    ("P", Code::KeyP, &["KeyP"]),
+    ("Q", Code::KeyQ, &["KeyQ"]),
+    ("R", Code::KeyR, &["KeyR"]),
+    ("S", Code::KeyS, &["KeyS"]),
+    ("T", Code::KeyT, &["KeyT"]),
+    ("U", Code::KeyU, &["KeyU"]),
+    ("V", Code::KeyV, &["KeyV"]),
+    ("W", Code::KeyW, &["KeyW"]),
+    ("X", Code::KeyX, &["KeyX"]),
+    ("Y", Code::KeyY, &["KeyY"]),
+    ("Z", Code::KeyZ, &["KeyZ"]),
+    ("0", Code::Digit0, &["Digit0"]),
+    ("1", Code::Digit1, &["Digit1"]),
+    ("2", Code::Digit2, &["Digit2"]),
+    ("3", Code::Digit3, &["Digit3"]),
+    ("4", Code::Digit4, &["Digit4"]),
+    ("5", Code::Digit5, &["Digit5"]),
+    ("6", Code::Digit6, &["Digit6"]),
+    ("7", Code::Digit7, &["Digit7"]),
+    ("8", Code::Digit8, &["Digit8"]),
+    ("9", Code::Digit9, &["Digit9"]),
+    // Punctuation
+    ("Backquote", Code::Backquote, &["`", "Grave"]),
+    ("Minus", Code::Minus, &["-"]),
+    ("Equal", Code::Equal, &["="]),
+    ("BracketLeft", Code::BracketLeft, &["["]),
+    ("BracketRight", Code::BracketRight, &["]"]),
+    ("Backslash", Code::Backslash, &["\\"]),
+    ("Semicolon", Code::Semicolon, &[";"]),
+    ("Quote", Code::Quote, &["'"]),
+    ("Comma", Code::Comma, &[","]),
+    ("Period", Code::Period, &["."]),
+    ("Slash", Code::Slash, &["/"]),
+    // Editing and navigation
+    ("Space", Code::Space, &[]),
+    ("Enter", Code::Enter, &["Return"]),
+    ("Tab", Code::Tab, &[]),
+    ("Escape", Code::Escape, &["Esc"]),
+    ("Backspace", Code::Backspace, &[]),
+    ("Delete", Code::Delete, &["Del"]),
+    ("Insert", Code::Insert, &["Ins"]),
+    ("Home", Code::Home, &[]),
+    ("End", Code::End, &[]),
+    ("PageUp", Code::PageUp, &["PgUp"]),
+    ("PageDown", Code::PageDown, &["PgDn"]),
+    ("Up", Code::ArrowUp, &["ArrowUp"]),
+    ("Down", Code::ArrowDown, &["ArrowDown"]),
+    ("Left", Code::ArrowLeft, &["ArrowLeft"]),
+    ("Right", Code::ArrowRight, &["ArrowRight"]),
+    ("PrintScreen", Code::PrintScreen, &["PrtSc"]),
+    ("ScrollLock", Code::ScrollLock, &[]),
+    ("Pause", Code::Pause, &["Break"]),
+    // Function keys
+    ("F1", Code::F1, &[]),
+    ("F2", Code::F2, &[]),
+    ("F3", Code::F3, &[]),
+    ("F4", Code::F4, &[]),
+    ("F5", Code::F5, &[]),
+    ("F6", Code::F6, &[]),
+    ("F7", Code::F7, &[]),
+    ("F8", Code::F8, &[]),
+    ("F9", Code::F9, &[]),
+    ("F10", Code::F10, &[]),
+    ("F11", Code::F11, &[]),
+    ("F12", Code::F12, &[]),
+    ("F13", Code::F13, &[]),
+    ("F14", Code::F14, &[]),
+    ("F15", Code::F15, &[]),
+    ("F16", Code::F16, &[]),
+    ("F17", Code::F17, &[]),
+    ("F18", Code::F18, &[]),
+    ("F19", Code::F19, &[]),
+    ("F20", Code::F20, &[]),
+    ("F21", Code::F21, &[]),
+    ("F22", Code::F22, &[]),
+    ("F23", Code::F23, &[]),
+    ("F24", Code::F24, &[]),
+    // Numpad
+    ("Num0", Code::Numpad0, &["Numpad0"]),
+    ("Num1", Code::Numpad1, &["Numpad1"]),
+    ("Num2", Code::Numpad2, &["Numpad2"]),
+    ("Num3", Code::Numpad3, &["Numpad3"]),
+    ("Num4", Code::Numpad4, &["Numpad4"]),
+    ("Num5", Code::Numpad5, &["Numpad5"]),
+    ("Num6", Code::Numpad6, &["Numpad6"]),
+    ("Num7", Code::Numpad7, &["Numpad7"]),
+    ("Num8", Code::Numpad8, &["Numpad8"]),
+    ("Num9", Code::Numpad9, &["Numpad9"]),
+    ("NumAdd", Code::NumpadAdd, &["NumpadAdd", "NumPlus"]),
+    (
+        "NumSubtract",
+        Code::NumpadSubtract,
+        &["NumpadSubtract", "NumMinus"],
+    ),
+    ("NumMultiply", Code::NumpadMultiply, &["NumpadMultiply"]),
+    ("NumDivide", Code::NumpadDivide, &["NumpadDivide"]),
+    ("NumDecimal", Code::NumpadDecimal, &["NumpadDecimal"]),
+    ("NumEnter", Code::NumpadEnter, &["NumpadEnter"]),
+    ("NumEqual", Code::NumpadEqual, &["NumpadEqual"]),
+    // Media
+    ("MediaPlayPause", Code::MediaPlayPause, &["PlayPause"]),
+    ("MediaStop", Code::MediaStop, &[]),
+    (
+        "MediaTrackNext",
+        Code::MediaTrackNext,
+        &["MediaNextTrack", "NextTrack"],
+    ),
+    (
+        "MediaTrackPrevious",
+        Code::MediaTrackPrevious,
+        &["MediaPrevTrack", "MediaTrackPrev", "PrevTrack"],
+    ),
+    ("VolumeUp", Code::AudioVolumeUp, &["AudioVolumeUp"]),
+    ("VolumeDown", Code::AudioVolumeDown, &["AudioVolumeDown"]),
+    ("VolumeMute", Code::AudioVolumeMute, &["AudioVolumeMute"]),
+];
+
+fn parse_key(token: &str) -> Option<Code> {
+    KEYS.iter()
+        .find(|(name, _, aliases)| {
+            name.eq_ignore_ascii_case(token)
+                || aliases.iter().any(|a| a.eq_ignore_ascii_case(token))
+        })
+        .map(|(_, code, _)| *code)
+}
+
+fn key_name(code: Code) -> Option<&'static str> {
+    KEYS.iter()
+        .find(|(_, c, _)| *c == code)
+        .map(|(name, _, _)| *name)
+}
+
+/// Keys that may be bound without a modifier
+fn is_standalone(code: Code) -> bool {
+    let name = key_name(code).unwrap_or_default();
+    (name.starts_with('F') && name.len() > 1 && name[1..].parse::<u8>().is_ok())
+        || name.starts_with("Media")
+        || name.starts_with("Volume")
+}
+
+/// Parse an accelerator like `CmdOrCtrl+Shift+O`
+///
+/// Tokens are separated by `+` and matched case-insensitively. Exactly one
+/// key is required, plus at least one modifier unless it is a function or
+/// media key.
+pub fn parse(accelerator: &str) -> Result<Shortcut, AcceleratorError> {
+    if accelerator.trim().is_empty() {
+        return Err(AcceleratorError::Empty("Shortcut is empty".to_string()));
+    }
+
+    let mut modifiers = Modifiers::empty();
+    let mut key: Option<(Code, &str)> = None;
+
+    for token in accelerator.split('+').map(str::trim) {
+        if token.is_empty() {
+            return Err(AcceleratorError::UnknownToken(format!(
+                "Empty key in `{}`",
+                accelerator
+            )));
+        }
+
+        if let Some(modifier) = parse_modifier(token) {
+            if modifiers.contains(modifier) {
+                return Err(AcceleratorError::DuplicateModifier(format!(
+                    "`{}` repeats a modifier in `{}`",
+                    token, accelerator
+                )));
+            }
+            modifiers |= modifier;
+            continue;
+        }
+
+        let Some(code) = parse_key(token) else {
+            return Err(AcceleratorError::UnknownToken(format!(
+                "Unknown key `{}` in `{}`",
+                token, accelerator
+            )));
+        };
+        if let Some((_, previous)) = key {
+            return Err(AcceleratorError::MultipleKeys(format!(
+                "`{}` and `{}` are both keys; a shortcut has one key",
+                previous, token
+            )));
+        }
+        key = Some((code, token));
+    }
+
+    let Some((code, _)) = key else {
+        return Err(AcceleratorError::MissingKey(format!(
+            "`{}` has no key, only modifiers",
+            accelerator
+        )));
+    };
+    if modifiers.is_empty() && !is_standalone(code) {
+        return Err(AcceleratorError::MissingModifier(format!(
+            "`{}` needs a modifier such as Ctrl or Alt",
+            accelerator
+        )));
+    }
+
+    Ok(Shortcut::new(Some(modifiers), code))
+}
+
+/// Canonical accelerator string, which `parse` turns back into the same shortcut
+pub fn format(shortcut: &Shortcut) -> String {
+    let mut parts = Vec::new();
+    for (modifier, name) in [
+        (Modifiers::SUPER, SUPER_NAME),
+        (Modifiers::CONTROL, "Ctrl"),
+        (Modifiers::ALT, "Alt"),
+        (Modifiers::SHIFT, "Shift"),
+    ] {
+        if shortcut.mods.contains(modifier) {
+            parts.push(name.to_string());
+        }
+    }
+    parts.push(
+        key_name(shortcut.key)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{:?}", shortcut.key)),
+    );
+    parts.join("+")
+}
+
+// --------------------------------------------
+// Commands
+// --------------------------------------------
+
+/// Check an accelerator before saving it, returns its canonical form
+#[tauri::command]
+pub fn validate_shortcut(shortcut: String) -> Result<String, AcceleratorError> {
+    parse(&shortcut).map(|s| format(&s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shortcut(mods: Modifiers, key: Code) -> Shortcut {
+        Shortcut::new(Some(mods), key)
+    }
+
+    #[test]
+    fn parses_valid_accelerators() {
+        let cases = [
+            (
+                "Ctrl+Shift+O",
+                shortcut(Modifiers::CONTROL | Modifiers::SHIFT, Code::KeyO),
+            ),
+            (
+                "ctrl+shift+o",
+                shortcut(Modifiers::CONTROL | Modifiers::SHIFT, Code::KeyO),
+            ),
+            (" Alt + Space ", shortcut(Modifiers::ALT, Code::Space)),
+            ("CmdOrCtrl+K", shortcut(PRIMARY, Code::KeyK)),
+            (
+                "CommandOrControl+Shift+P",
+                shortcut(PRIMARY | Modifiers::SHIFT, Code::KeyP),
+            ),
+            (
+                "Cmd+Option+I",
+                shortcut(Modifiers::SUPER | Modifiers::ALT, Code::KeyI),
+            ),
+            ("Super+1", shortcut(Modifiers::SUPER, Code::Digit1)),
+            ("Ctrl+Digit5", shortcut(Modifiers::CONTROL, Code::Digit5)),
+            ("Ctrl+/", shortcut(Modifiers::CONTROL, Code::Slash)),
+            ("Ctrl+Slash", shortcut(Modifiers::CONTROL, Code::Slash)),
+            ("Ctrl+`", shortcut(Modifiers::CONTROL, Code::Backquote)),
+            ("Ctrl+-", shortcut(Modifiers::CONTROL, Code::Minus)),
+            ("Ctrl+=", shortcut(Modifiers::CONTROL, Code::Equal)),
+            ("Ctrl+[", shortcut(Modifiers::CONTROL, Code::BracketLeft)),
+            ("Ctrl+\\", shortcut(Modifiers::CONTROL, Code::Backslash)),
+            ("Ctrl+;", shortcut(Modifiers::CONTROL, Code::Semicolon)),
+            ("Ctrl+'", shortcut(Modifiers::CONTROL, Code::Quote)),
+            ("Ctrl+,", shortcut(Modifiers::CONTROL, Code::Comma)),
+            ("Ctrl+.", shortcut(Modifiers::CONTROL, Code::Period)),
+            ("Ctrl+Num5", shortcut(Modifiers::CONTROL, Code::Numpad5)),
+            (
+                "Ctrl+NumpadAdd",
+                shortcut(Modifiers::CONTROL, Code::NumpadAdd),
+            ),
+            ("Alt+NumEnter", shortcut(Modifiers::ALT, Code::NumpadEnter)),
+            ("Shift+PgDn", shortcut(Modifiers::SHIFT, Code::PageDown)),
+            ("Ctrl+ArrowUp", shortcut(Modifiers::CONTROL, Code::ArrowUp)),
+            ("Ctrl+Esc", shortcut(Modifiers::CONTROL, Code::Escape)),
+            ("F13", shortcut(Modifiers::empty(), Code::F13)),
+            ("Shift+F24", shortcut(Modifiers::SHIFT, Code::F24)),
+            (
+                "MediaPlayPause",
+                shortcut(Modifiers::empty(), Code::MediaPlayPause),
+            ),
+            (
+                "MediaPrevTrack",
+                shortcut(Modifiers::empty(), Code::MediaTrackPrevious),
+            ),
+            (
+                "VolumeMute",
+                shortcut(Modifiers::empty(), Code::AudioVolumeMute),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse(input), Ok(expected), "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_accelerators() {
+        let cases = [
+            ("", AcceleratorError::Empty("Shortcut is empty".into())),
+            (
+                "Ctrl+Foo",
+                AcceleratorError::UnknownToken("Unknown key `Foo` in `Ctrl+Foo`".into()),
+            ),
+            (
+                "Ctrl++O",
+                AcceleratorError::UnknownToken("Empty key in `Ctrl++O`".into()),
+            ),
+            (
+                "Hyper+O",
+                AcceleratorError::UnknownToken("Unknown key `Hyper` in `Hyper+O`".into()),
+            ),
+            (
+                "Ctrl+A+B",
+                AcceleratorError::MultipleKeys(
+                    "`A` and `B` are both keys; a shortcut has one key".into(),
+                ),
+            ),
+            (
+                "Ctrl+Control+O",
+                AcceleratorError::DuplicateModifier(
+                    "`Control` repeats a modifier in `Ctrl+Control+O`".into(),
+                ),
+            ),
+            (
+                "Ctrl+Shift",
+                AcceleratorError::MissingKey("`Ctrl+Shift` has no key, only modifiers".into()),
+            ),
+            (
+                "O",
+                AcceleratorError::MissingModifier(
+                    "`O` needs a modifier such as Ctrl or Alt".into(),
+                ),
+            ),
+            (
+                "Num1",
+                AcceleratorError::MissingModifier(
+                    "`Num1` needs a modifier such as Ctrl or Alt".into(),
+                ),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse(input), Err(expected), "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn formats_canonically() {
+        let cases = [
+            ("shift+ctrl+o", "Ctrl+Shift+O"),
+            ("Alt+return", "Alt+Enter"),
+            ("Ctrl+/", "Ctrl+Slash"),
+            ("Ctrl+numpadadd", "Ctrl+NumAdd"),
+            ("Shift+Alt+Ctrl+ArrowLeft", "Ctrl+Alt+Shift+Left"),
+            ("mediatrackprev", "MediaTrackPrevious"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse(input).map(|s| format(&s)).as_deref(), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn every_key_round_trips() {
+        let modifier_sets = [
+            Modifiers::CONTROL,
+            Modifiers::SUPER | Modifiers::SHIFT,
+            Modifiers::CONTROL | Modifiers::ALT | Modifiers::SHIFT | Modifiers::SUPER,
+        ];
+
+        for (name, code, aliases) in KEYS {
+            for mods in modifier_sets {
+                let original = shortcut(mods, *code);
+                assert_eq!(
+                    parse(&format(&original)),
+                    Ok(original),
+                    "round trip of {}",
+                    name
+                );
+            }
+            for alias in *aliases {
+                assert_eq!(parse_key(alias), Some(*code), "alias {} of {}", alias, name);
+            }
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri::{AppHandle, Manager};
+
+use crate::permissions::PermissionData;
+
+const LOG_FILE: &str = "permission-log.jsonl";
+
+// Rotate once the active file grows past this size, keeping this many old files
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+const MAX_ROTATED_FILES: usize = 3;
+
+// Serializes appends and rotation
+static LOG_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditDecision {
+    Once,
+    Always,
+    Reject,
+    /// Dropped from the queue without an answer
+    Dismissed,
+    /// Answered outside OpenTray, e.g. in the TUI or the web UI
+    #[serde(rename = "resolved_elsewhere")]
+    ResolvedElsewhere,
+}
+
+impl From<opentray_client::PermissionReply> for AuditDecision {
+    fn from(reply: opentray_client::PermissionReply) -> Self {
+        match reply {
+            opentray_client::PermissionReply::Once => AuditDecision::Once,
+            opentray_client::PermissionReply::Always => AuditDecision::Always,
+            opentray_client::PermissionReply::Reject => AuditDecision::Reject,
+        }
+    }
+}
+
+/// One line of the permission audit log
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+    pub instance_url: String,
+    pub session_title: String,
+    pub tool: String,
+    pub patterns: Vec<String>,
+    pub decision: AuditDecision,
+    /// Answered by a policy rule rather than by the user
+    pub automatic: bool,
+    /// For `resolved_elsewhere`, the answer given there when the event reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply: Option<AuditDecision>,
+    /// Why the answer couldn't be sent; the decision didn't take effect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    pub fn new(data: &PermissionData, decision: AuditDecision, automatic: bool) -> Self {
+        let request = &data.request;
+        Self {
+            timestamp: now_millis(),
+            instance_url: data.instance_url.clone(),
+            session_title: data.session_title.clone(),
+            tool: request
+                .get("permission")
+                .and_then(|p| p.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            patterns: request
+                .get("patterns")
+                .and_then(|p| p.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|i| i.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            decision,
+            automatic,
+            reply: None,
+            error: None,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFilter {
+    /// Only records at or after this time (Unix ms)
+    #[serde(default)]
+    pub since: Option<u64>,
+    /// Only records before this time (Unix ms)
+    #[serde(default)]
+    pub until: Option<u64>,
+    #[serde(default)]
+    pub instance_url: Option<String>,
+    #[serde(default)]
+    pub decision: Option<AuditDecision>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp < until)
+            && self
+                .instance_url
+                .as_ref()
+                .is_none_or(|url| &record.instance_url == url)
+            && self.decision.is_none_or(|d| record.decision == d)
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn log_dir(app_handle: &AppHandle) -> PathBuf {
+    app_handle
+        .path()
+        .app_data_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+}
+
+/// `permission-log.jsonl` for index 0, `permission-log.jsonl.N` for rotated files
+fn log_path(dir: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(LOG_FILE)
+    } else {
+        dir.join(format!("{}.{}", LOG_FILE, index))
+    }
+}
+
+/// Append a record, rotating the log first if it grew too large
+pub fn record(app_handle: &AppHandle, record: &AuditRecord) {
+    if let Err(e) = append(&log_dir(app_handle), record) {
+        log::error!("failed to write permission log: {}", e);
+    }
+}
+
+fn append(dir: &Path, record: &AuditRecord) -> std::io::Result<()> {
+    let _guard = LOG_LOCK.lock().map_err(|e| std::io::Error::other(e.to_string()))?;
+
+    std::fs::create_dir_all(dir)?;
+    rotate_if_needed(dir)?;
+
+    let mut line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path(dir, 0))?;
+    file.write_all(line.as_bytes())
+}
+
+fn rotate_if_needed(dir: &Path) -> std::io::Result<()> {
+    let current = log_path(dir, 0);
+    let size = std::fs::metadata(&current).map(|m| m.len()).unwrap_or(0);
+    if size < MAX_LOG_SIZE {
+        return Ok(());
+    }
+
+    // Shift .1 -> .2 -> .3, dropping the oldest
+    let _ = std::fs::remove_file(log_path(dir, MAX_ROTATED_FILES));
+    for index in (0..MAX_ROTATED_FILES).rev() {
+        let from = log_path(dir, index);
+        if from.exists() {
+            std::fs::rename(&from, log_path(dir, index + 1))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read records from the active and rotated files, newest first
+fn query(dir: &Path, filter: &AuditFilter) -> Vec<AuditRecord> {
+    let _guard = LOG_LOCK.lock();
+
+    let mut records = Vec::new();
+    for index in (0..=MAX_ROTATED_FILES).rev() {
+        let Ok(file) = std::fs::File::open(log_path(dir, index)) else {
+            continue;
+        };
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            // Skip lines truncated by a crash
+            if let Ok(record) = serde_json::from_str::<AuditRecord>(&line) {
+                if filter.matches(&record) {
+                    records.push(record);
+                }
+            }
+        }
+    }
+
+    records.reverse();
+    if let Some(limit) = filter.limit {
+        records.truncate(limit);
+    }
+    records
+}
+
+// --------------------------------------------
+// Commands
+// --------------------------------------------
+
+/// Query the permission audit log, newest records first
+#[tauri::command]
+pub fn query_permission_log(
+    app_handle: AppHandle,
+    filter: Option<AuditFilter>,
+) -> Result<Vec<AuditRecord>, String> {
+    Ok(query(&log_dir(&app_handle), &filter.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: u64, instance_url: &str, decision: AuditDecision) -> AuditRecord {
+        AuditRecord {
+            timestamp,
+            instance_url: instance_url.to_string(),
+            session_title: "Session".to_string(),
+            tool: "bash".to_string(),
+            patterns: vec!["ls".to_string()],
+            decision,
+            automatic: false,
+            reply: None,
+            error: None,
+        }
+    }
+
+    fn read(dir: &Path, index: usize) -> String {
+        std::fs::read_to_string(log_path(dir, index)).unwrap()
+    }
+
+    fn timestamps(records: &[AuditRecord]) -> Vec<u64> {
+        records.iter().map(|r| r.timestamp).collect()
+    }
+
+    #[test]
+    fn rotates_past_the_size_limit_and_keeps_three_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for index in 1..=MAX_ROTATED_FILES {
+            std::fs::write(log_path(dir, index), format!("old {}\n", index)).unwrap();
+        }
+
+        // Below the limit nothing moves
+        append(dir, &record(1, "a", AuditDecision::Once)).unwrap();
+        assert!(log_path(dir, 0).exists());
+        assert_eq!(read(dir, 1), "old 1\n");
+
+        std::fs::write(log_path(dir, 0), vec![b'x'; MAX_LOG_SIZE as usize]).unwrap();
+        append(dir, &record(2, "a", AuditDecision::Once)).unwrap();
+
+        assert_eq!(read(dir, 0).lines().count(), 1);
+        assert_eq!(
+            std::fs::metadata(log_path(dir, 1)).unwrap().len(),
+            MAX_LOG_SIZE
+        );
+        assert_eq!(read(dir, 2), "old 1\n");
+        assert_eq!(read(dir, 3), "old 2\n");
+        assert!(!log_path(dir, MAX_ROTATED_FILES + 1).exists());
+    }
+
+    #[test]
+    fn queries_across_rotated_files_newest_first() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(
+            log_path(dir, 1),
+            format!(
+                "{}\n{{truncated\n",
+                serde_json::to_string(&record(1, "a", AuditDecision::Once)).unwrap()
+            ),
+        )
+        .unwrap();
+        append(dir, &record(2, "a", AuditDecision::Once)).unwrap();
+
+        assert_eq!(timestamps(&query(dir, &AuditFilter::default())), vec![2, 1]);
+    }
+
+    #[test]
+    fn filters_by_time_instance_decision_and_limit() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        for (timestamp, url, decision) in [
+            (10, "a", AuditDecision::Once),
+            (20, "b", AuditDecision::Reject),
+            (30, "a", AuditDecision::Always),
+            (40, "a", AuditDecision::Reject),
+        ] {
+            append(dir, &record(timestamp, url, decision)).unwrap();
+        }
+
+        let filter = |filter: AuditFilter| timestamps(&query(dir, &filter));
+        assert_eq!(
+            filter(AuditFilter {
+                since: Some(20),
+                until: Some(40),
+                ..Default::default()
+            }),
+            vec![30, 20]
+        );
+        assert_eq!(
+            filter(AuditFilter {
+                instance_url: Some("a".to_string()),
+                ..Default::default()
+            }),
+            vec![40, 30, 10]
+        );
+        assert_eq!(
+            filter(AuditFilter {
+                decision: Some(AuditDecision::Reject),
+                ..Default::default()
+            }),
+            vec![40, 20]
+        );
+        assert_eq!(
+            filter(AuditFilter {
+                limit: Some(2),
+                ..Default::default()
+            }),
+            vec![40, 30]
+        );
+    }
+
+    #[test]
+    fn keeps_the_error_of_failed_answers() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut failed = record(1, "a", AuditDecision::Once);
+        failed.error = Some("Instance unreachable".to_string());
+        append(temp.path(), &failed).unwrap();
+        append(temp.path(), &record(2, "a", AuditDecision::Once)).unwrap();
+
+        let records = query(temp.path(), &AuditFilter::default());
+        assert_eq!(records[0].error, None);
+        assert_eq!(records[1].error.as_deref(), Some("Instance unreachable"));
+    }
+
+    #[test]
+    fn keeps_the_reply_of_requests_resolved_elsewhere() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut resolved = record(1, "a", AuditDecision::ResolvedElsewhere);
+        resolved.reply = Some(AuditDecision::Always);
+        append(temp.path(), &resolved).unwrap();
+        let answered = record(2, "a", AuditDecision::ResolvedElsewhere);
+        append(temp.path(), &answered).unwrap();
+
+        assert!(read(temp.path(), 0).contains(r#""decision":"resolved_elsewhere","#));
+        let filter = AuditFilter {
+            decision: Some(AuditDecision::ResolvedElsewhere),
+            ..Default::default()
+        };
+        let records = query(temp.path(), &filter);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].reply, None);
+        assert_eq!(records[1].reply, Some(AuditDecision::Always));
+    }
+}
@@ -0,0 +1,263 @@
+use std::sync::LazyLock;
+
+use ab_glyph::{point, Font, FontRef, Glyph, PxScale, Rect, ScaleFont};
+use image::{Rgba, RgbaImage};
+
+// Digits and `+` only, see fonts/README.md
+static FONT: LazyLock<FontRef<'static>> = LazyLock::new(|| {
+    FontRef::try_from_slice(include_bytes!("../fonts/badge.ttf")).expect("bundled badge font")
+});
+
+const BADGE_COLOR: Rgba<u8> = Rgba([255, 59, 48, 255]);
+const TEXT_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Badge text for a count: none for zero, "9+" past nine
+pub fn label(count: usize) -> Option<String> {
+    match count {
+        0 => None,
+        1..=9 => Some(count.to_string()),
+        _ => Some("9+".to_string()),
+    }
+}
+
+/// Draw a badge showing `text` in the top-right corner of `icon`
+///
+/// Everything is sized from the icon, so a 32px (1x) and a 64px (2x) icon get
+/// the same badge. Longer text stretches the circle into a pill.
+pub fn draw(icon: &mut RgbaImage, text: &str) {
+    let (width, height) = icon.dimensions();
+    let diameter = width.min(height) as f32 / 2.0;
+    let radius = diameter / 2.0;
+
+    let scale = PxScale::from(diameter * 0.78);
+    let glyphs = layout(text, scale);
+    let text_bounds = bounds(&glyphs, scale);
+    let badge_width = match text_bounds {
+        Some(b) => (b.width() + radius).max(diameter),
+        None => diameter,
+    };
+
+    let right = width as f32;
+    let (start, end) = (right - badge_width + radius, right - radius);
+    fill_pill(icon, (start, end), radius, radius, |pixel, coverage| {
+        blend(pixel, BADGE_COLOR, coverage)
+    });
+
+    let Some(text_bounds) = text_bounds else {
+        return;
+    };
+    let offset = point(
+        (start + end) / 2.0 - (text_bounds.min.x + text_bounds.max.x) / 2.0,
+        radius - (text_bounds.min.y + text_bounds.max.y) / 2.0,
+    );
+    for mut glyph in glyphs {
+        glyph.position += offset;
+        let Some(outline) = FONT.outline_glyph(glyph) else {
+            continue;
+        };
+        let b = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let x = b.min.x as i32 + gx as i32;
+            let y = b.min.y as i32 + gy as i32;
+            if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+                blend(icon.get_pixel_mut(x as u32, y as u32), TEXT_COLOR, coverage);
+            }
+        });
+    }
+}
+
+/// Glyphs for `text` on a baseline at y = 0
+fn layout(text: &str, scale: PxScale) -> Vec<Glyph> {
+    let font = FONT.as_scaled(scale);
+    let mut x = 0.0;
+    text.chars()
+        .map(|c| {
+            let glyph = font.scaled_glyph(c);
+            let advance = font.h_advance(glyph.id);
+            let positioned = Glyph {
+                position: point(x, 0.0),
+                ..glyph
+            };
+            x += advance;
+            positioned
+        })
+        .collect()
+}
+
+/// Exact ink bounds of the laid out glyphs, y growing downwards
+fn bounds(glyphs: &[Glyph], scale: PxScale) -> Option<Rect> {
+    let factor = FONT.as_scaled(scale).scale_factor();
+    glyphs
+        .iter()
+        .filter_map(|g| {
+            let ink = FONT.outline(g.id)?.bounds;
+            Some(Rect {
+                min: point(
+                    g.position.x + ink.min.x * factor.horizontal,
+                    g.position.y - ink.max.y * factor.vertical,
+                ),
+                max: point(
+                    g.position.x + ink.max.x * factor.horizontal,
+                    g.position.y - ink.min.y * factor.vertical,
+                ),
+            })
+        })
+        .reduce(|a, b| Rect {
+            min: point(a.min.x.min(b.min.x), a.min.y.min(b.min.y)),
+            max: point(a.max.x.max(b.max.x), a.max.y.max(b.max.y)),
+        })
+}
+
+/// Draw a status dot in the bottom-right corner of `icon`
+///
+/// A transparent ring around the dot keeps it readable on top of the glyph.
+pub fn dot(icon: &mut RgbaImage, color: Rgba<u8>) {
+    let size = icon.width().min(icon.height()) as f32;
+    let radius = size * 0.16;
+    let gap = size * 0.06;
+    let center = size - radius - gap / 2.0;
+
+    fill_pill(
+        icon,
+        (center, center),
+        center,
+        radius + gap,
+        |pixel, coverage| {
+            let keep = 1.0 - coverage.clamp(0.0, 1.0);
+            pixel[3] = (pixel[3] as f32 * keep).round() as u8;
+        },
+    );
+    fill_pill(icon, (center, center), center, radius, |pixel, coverage| {
+        blend(pixel, color, coverage)
+    });
+}
+
+/// Visit the pixels of a horizontal pill with their anti-aliased coverage
+///
+/// The pill spans circle centers from `x.0` to `x.1` on row `y`; coverage comes
+/// from the distance to its edge.
+fn fill_pill(
+    icon: &mut RgbaImage,
+    x: (f32, f32),
+    y: f32,
+    radius: f32,
+    mut paint: impl FnMut(&mut Rgba<u8>, f32),
+) {
+    let (width, height) = icon.dimensions();
+    let (start, end) = x;
+    let rows = (y - radius).floor().max(0.0) as u32..((y + radius).ceil() as u32).min(height);
+    let columns =
+        (start - radius).floor().max(0.0) as u32..((end + radius).ceil() as u32).min(width);
+    for py in rows {
+        for px in columns.clone() {
+            let (cx, cy) = (px as f32 + 0.5, py as f32 + 0.5);
+            let dx = cx - cx.clamp(start, end);
+            let dy = cy - y;
+            let coverage = radius + 0.5 - (dx * dx + dy * dy).sqrt();
+            if coverage > 0.0 {
+                paint(icon.get_pixel_mut(px, py), coverage);
+            }
+        }
+    }
+}
+
+/// Source-over `color` onto `dst` at `coverage` (clamped to 0..=1)
+fn blend(dst: &mut Rgba<u8>, color: Rgba<u8>, coverage: f32) {
+    let alpha = coverage.clamp(0.0, 1.0) * color[3] as f32 / 255.0;
+    if alpha <= 0.0 {
+        return;
+    }
+    let dst_alpha = dst[3] as f32 / 255.0;
+    let out_alpha = alpha + dst_alpha * (1.0 - alpha);
+    for i in 0..3 {
+        let value =
+            (color[i] as f32 * alpha + dst[i] as f32 * dst_alpha * (1.0 - alpha)) / out_alpha;
+        dst[i] = value.round() as u8;
+    }
+    dst[3] = (out_alpha * 255.0).round() as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // Set UPDATE_GOLDEN=1 to rewrite the reference images after an intended change
+    fn assert_golden(name: &str, image: &RgbaImage) {
+        let mut png = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(format!("{}.png", name));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &png).unwrap();
+            return;
+        }
+
+        let expected = std::fs::read(&path)
+            .unwrap_or_else(|e| panic!("missing golden {}: {}", path.display(), e));
+        assert!(
+            png == expected,
+            "{} differs from {}, rerun with UPDATE_GOLDEN=1 if intended",
+            name,
+            path.display()
+        );
+    }
+
+    #[test]
+    fn labels_counts() {
+        let cases = [
+            (0, None),
+            (1, Some("1")),
+            (9, Some("9")),
+            (10, Some("9+")),
+            (250, Some("9+")),
+        ];
+        for (count, expected) in cases {
+            assert_eq!(label(count).as_deref(), expected, "count {}", count);
+        }
+    }
+
+    #[test]
+    fn badges_match_golden_images() {
+        for (size, scale) in [(32, 1), (64, 2)] {
+            for text in ["1", "7", "9+"] {
+                let mut icon = RgbaImage::new(size, size);
+                draw(&mut icon, text);
+                let name = format!("badge-{}@{}x", text.replace('+', "plus"), scale);
+                assert_golden(&name, &icon);
+            }
+        }
+    }
+
+    #[test]
+    fn badge_is_anti_aliased() {
+        for size in [32, 64] {
+            let mut icon = RgbaImage::new(size, size);
+            draw(&mut icon, "8");
+
+            // Partial coverage on the circle's edge and inside the glyph
+            let edge = icon.pixels().filter(|p| p[3] > 0 && p[3] < 255).count();
+            let text = icon
+                .pixels()
+                .filter(|p| p[3] == 255 && p[1] > BADGE_COLOR[1] && p[1] < 255)
+                .count();
+            assert!(edge > 0, "hard circle edge at {}px", size);
+            assert!(text > 0, "hard text edge at {}px", size);
+        }
+    }
+
+    #[test]
+    fn draws_over_existing_icon() {
+        let mut icon = RgbaImage::from_pixel(32, 32, Rgba([0, 0, 0, 255]));
+        draw(&mut icon, "3");
+
+        // Bottom-left stays untouched, the badge sits top-right
+        assert_eq!(icon.get_pixel(0, 31), &Rgba([0, 0, 0, 255]));
+        assert_eq!(icon.get_pixel(24, 2)[0], 255);
+    }
+}
@@ -1,37 +1,78 @@
-use std::path::PathBuf;
-use std::sync::{Mutex, Once};
+use std::sync::Once;
 
 use tauri::{Emitter, Manager};
 #[cfg(target_os = "macos")]
 use tauri_nspanel::ManagerExt;
 use tauri_plugin_notification::NotificationExt;
 
-use crate::fns::update_tray_icon_with_badge;
+use crate::audit::{self, AuditDecision, AuditRecord};
+use crate::permissions::{self, PermissionData};
+use crate::policy;
+use crate::sandbox::{self, FileAccessError};
+use crate::settings;
+use crate::storage;
 #[cfg(target_os = "macos")]
 use crate::fns::{
     position_panel, position_permission_popup, setup_panel_listeners, swizzle_to_panel,
     update_panel_appearance,
 };
 
-// Store pending permission data
-static PENDING_PERMISSION: Mutex<Option<serde_json::Value>> = Mutex::new(None);
-
 static INIT: Once = Once::new();
 
 // App settings stored in memory and synced to disk
-#[derive(serde::Deserialize, serde::Serialize, Clone, Default)]
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
 pub struct AppSettings {
+    /// Settings file format, upgraded by `settings::migrate`
+    #[serde(default)]
+    pub version: u32,
     #[serde(default)]
     pub autostart: bool,
     #[serde(default)]
     pub sound_enabled: bool,
     #[serde(default)]
     pub compact_mode: bool,
-    #[serde(default = "default_shortcut")]
-    pub global_shortcut: String,
+    /// Pulse the tray icon while a session is busy
+    #[serde(default = "default_animate_tray_icon")]
+    pub animate_tray_icon: bool,
+    /// Accelerator per action name (see `shortcuts::ShortcutAction`), empty for unbound
+    #[serde(default = "crate::shortcuts::default_bindings")]
+    pub shortcuts: std::collections::BTreeMap<String, String>,
+    #[serde(default = "default_discovery_port_start")]
+    pub discovery_port_start: u16,
+    #[serde(default = "default_discovery_port_count")]
+    pub discovery_port_count: u16,
+    #[serde(default)]
+    pub permission_rules: Vec<policy::PermissionRule>,
+    #[serde(default)]
+    pub notifications: crate::notifications::NotificationSettings,
+    /// Per-category sounds, played when `sound_enabled` is on
+    #[serde(default)]
+    pub sounds: crate::sound::SoundSettings,
+    /// Keys this build doesn't know, kept so they survive a save
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            version: crate::settings::CURRENT_VERSION,
+            autostart: false,
+            sound_enabled: false,
+            compact_mode: false,
+            animate_tray_icon: default_animate_tray_icon(),
+            shortcuts: crate::shortcuts::default_bindings(),
+            discovery_port_start: default_discovery_port_start(),
+            discovery_port_count: default_discovery_port_count(),
+            permission_rules: Vec::new(),
+            notifications: Default::default(),
+            sounds: Default::default(),
+            extra: serde_json::Map::new(),
+        }
+    }
 }
 
-fn default_shortcut() -> String {
+pub fn default_shortcut() -> String {
     if cfg!(target_os = "macos") {
         "Cmd+Shift+O".to_string()
     } else {
@@ -39,6 +80,18 @@ fn default_shortcut() -> String {
     }
 }
 
+fn default_animate_tray_icon() -> bool {
+    true
+}
+
+fn default_discovery_port_start() -> u16 {
+    crate::discovery::DEFAULT_PORT_START
+}
+
+fn default_discovery_port_count() -> u16 {
+    crate::discovery::DEFAULT_PORT_COUNT
+}
+
 #[tauri::command]
 pub fn init(app_handle: tauri::AppHandle) {
     INIT.call_once(|| {
@@ -61,6 +114,7 @@ pub fn show_panel(app_handle: tauri::AppHandle) {
 }
 
 pub fn show_panel_internal(app_handle: &tauri::AppHandle) {
+    crate::tray_icon::clear_errors(app_handle);
     #[cfg(target_os = "macos")]
     {
         let panel = app_handle.get_webview_panel("main").unwrap();
@@ -111,6 +165,7 @@ pub fn toggle_panel_internal(app_handle: &tauri::AppHandle) {
         if panel.is_visible() {
             panel.order_out(None);
         } else {
+            crate::tray_icon::clear_errors(app_handle);
             position_panel(app_handle, 0.0);
             panel.show();
         }
@@ -121,6 +176,7 @@ pub fn toggle_panel_internal(app_handle: &tauri::AppHandle) {
             if window.is_visible().unwrap_or(false) {
                 let _ = window.hide();
             } else {
+                crate::tray_icon::clear_errors(app_handle);
                 crate::tray::position_window_near_tray(&window);
                 let _ = window.show();
                 let _ = window.set_focus();
@@ -146,7 +202,7 @@ pub fn send_notification(
 
 #[derive(serde::Deserialize)]
 pub struct TrayIconUpdate {
-    pub badge: Option<String>,
+    pub badge: Option<usize>,
 }
 
 #[tauri::command]
@@ -154,148 +210,216 @@ pub fn update_tray_icon(
     app_handle: tauri::AppHandle,
     update: TrayIconUpdate,
 ) -> Result<(), String> {
-    update_tray_icon_with_badge(&app_handle, update.badge.as_deref()).map_err(|e| e.to_string())
+    crate::tray_icon::set_count(&app_handle, update.badge.unwrap_or(0));
+    Ok(())
 }
 
 #[tauri::command]
 pub fn update_tray_icon_theme(
     app_handle: tauri::AppHandle,
-    theme: String,
+    theme: crate::tray_icon::TrayTheme,
 ) -> Result<(), String> {
-    #[cfg(not(target_os = "macos"))]
-    crate::tray::update_icon_for_theme(&app_handle, &theme).map_err(|e| e.to_string())?;
-    
-    #[cfg(target_os = "macos")]
-    let _ = (app_handle, theme); // Unused on macOS
-
+    crate::tray_icon::set_theme(&app_handle, theme);
     Ok(())
 }
 
+// File commands are limited to instance directories and config dirs (see sandbox.rs)
+
 #[tauri::command]
-pub fn read_file(path: String) -> Result<String, String> {
-    let path = PathBuf::from(path);
-    std::fs::read_to_string(&path).map_err(|e| e.to_string())
+pub fn read_file(app_handle: tauri::AppHandle, path: String) -> Result<String, FileAccessError> {
+    let path = sandbox::resolve(&app_handle, &path)?;
+    Ok(std::fs::read_to_string(&path)?)
 }
 
 #[tauri::command]
-pub fn write_file(path: String, content: String) -> Result<(), String> {
-    let path = PathBuf::from(path);
-    std::fs::write(&path, content).map_err(|e| e.to_string())
+pub fn write_file(
+    app_handle: tauri::AppHandle,
+    path: String,
+    content: String,
+) -> Result<(), FileAccessError> {
+    let path = sandbox::resolve(&app_handle, &path)?;
+    Ok(storage::write_atomic(&path, content.as_bytes())?)
+}
+
+/// Roll a file written by `write_file` or `save_settings` back to its `.bak`
+#[tauri::command]
+pub fn restore_backup(app_handle: tauri::AppHandle, path: String) -> Result<(), FileAccessError> {
+    let path = sandbox::resolve(&app_handle, &path)?;
+    storage::restore_backup(&path)?;
+
+    // Rolling back settings.json has to reach the in-memory copy too
+    if settings::path(&app_handle)
+        .canonicalize()
+        .is_ok_and(|settings_path| settings_path == path)
+    {
+        app_handle
+            .state::<settings::SettingsStore>()
+            .reload(&app_handle)
+            .map_err(FileAccessError::Io)?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
-pub fn file_exists(path: String) -> bool {
-    PathBuf::from(path).exists()
+pub fn file_exists(app_handle: tauri::AppHandle, path: String) -> Result<bool, FileAccessError> {
+    Ok(sandbox::resolve(&app_handle, &path)?.exists())
 }
 
 // --------------------------------------------
 // Settings Commands
 // --------------------------------------------
 
-fn get_settings_path(app_handle: &tauri::AppHandle) -> PathBuf {
-    app_handle
-        .path()
-        .app_config_dir()
-        .unwrap_or_else(|_| PathBuf::from("."))
-        .join("settings.json")
+#[tauri::command]
+pub fn get_settings(
+    store: tauri::State<'_, settings::SettingsStore>,
+) -> Result<AppSettings, String> {
+    Ok(store.get())
 }
 
 #[tauri::command]
-pub fn get_settings(app_handle: tauri::AppHandle) -> Result<AppSettings, String> {
-    let path = get_settings_path(&app_handle);
-    if path.exists() {
-        let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        serde_json::from_str(&content).map_err(|e| e.to_string())
-    } else {
-        Ok(AppSettings::default())
-    }
+pub fn save_settings(
+    app_handle: tauri::AppHandle,
+    store: tauri::State<'_, settings::SettingsStore>,
+    settings: AppSettings,
+) -> Result<(), String> {
+    store.set(&app_handle, settings)
 }
 
+// --------------------------------------------
+// Permission Popup Commands
+// --------------------------------------------
+
+/// Queue a permission request and show the popup for the oldest one
 #[tauri::command]
-pub fn save_settings(app_handle: tauri::AppHandle, settings: AppSettings) -> Result<(), String> {
-    let path = get_settings_path(&app_handle);
+pub fn show_permission_popup(
+    app_handle: tauri::AppHandle,
+    data: PermissionData,
+) -> Result<(), String> {
+    receive_permission(&app_handle, data)
+}
 
-    // Ensure parent directory exists
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+/// Entry point for every incoming permission request, from the webview or the event stream
+/// Requests matching an auto-approval rule are answered without showing the popup.
+/// A request without an id could never be answered and is refused.
+pub fn receive_permission(
+    app_handle: &tauri::AppHandle,
+    data: PermissionData,
+) -> Result<(), String> {
+    let request_id = data.key();
+    if request_id.is_empty() {
+        return Err("Permission request has no id".to_string());
     }
 
-    // Update autostart setting
-    use tauri_plugin_autostart::ManagerExt as AutostartManagerExt;
-    let autostart = app_handle.autolaunch();
-    if settings.autostart {
-        let _ = autostart.enable();
-    } else {
-        let _ = autostart.disable();
+    let directory = crate::discovery::instances()
+        .into_iter()
+        .find(|i| i.url == data.instance_url)
+        .map(|i| i.directory)
+        .unwrap_or_default();
+    let decision = policy::evaluate(&data, &directory);
+
+    let instance_url = data.instance_url.clone();
+    let tool = data.request.get("permission").cloned().unwrap_or_default();
+
+    // Already queued or answered, the first arrival handles it
+    if !permissions::push(data) {
+        return Ok(());
     }
 
-    // Save settings to disk
-    let content = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
-    std::fs::write(&path, content).map_err(|e| e.to_string())?;
+    log::info!(
+        "{} {} from {} -> {:?} (rule {:?})",
+        request_id,
+        tool,
+        instance_url,
+        decision.action,
+        decision.rule
+    );
 
+    let reply = match decision.action {
+        policy::RuleAction::Allow => opentray_client::PermissionReply::Once,
+        policy::RuleAction::Deny => opentray_client::PermissionReply::Reject,
+        policy::RuleAction::Ask => {
+            ask_user(app_handle, &request_id);
+            return Ok(());
+        }
+    };
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = answer_permission(&handle, instance_url, &request_id, reply, true).await {
+            log::warn!("failed to answer {}: {}", request_id, e);
+            // Still queued, so it's up to the user now
+            if permissions::get(&request_id).is_some() {
+                ask_user(&handle, &request_id);
+            }
+        }
+    });
     Ok(())
 }
 
-// --------------------------------------------
-// Permission Popup Commands
-// --------------------------------------------
-
-#[derive(serde::Deserialize, serde::Serialize, Clone)]
-pub struct PermissionData {
-    pub request: serde_json::Value,
-    #[serde(rename = "sessionTitle")]
-    pub session_title: String,
-    #[serde(rename = "instanceUrl")]
-    pub instance_url: String,
+/// Notify about a queued request and show it in the popup
+fn ask_user(app_handle: &tauri::AppHandle, request_id: &str) {
+    if let Some(data) = permissions::get(request_id) {
+        crate::notifications::permission_required(app_handle, &data);
+    }
+    permissions_changed(app_handle);
+    present_permission_popup(app_handle);
 }
 
-#[tauri::command]
-pub fn show_permission_popup(
-    app_handle: tauri::AppHandle,
-    data: PermissionData,
-) -> Result<(), String> {
-    // Store the permission data
-    {
-        let mut pending = PENDING_PERMISSION.lock().map_err(|e| e.to_string())?;
-        *pending = Some(serde_json::to_value(&data).map_err(|e| e.to_string())?);
+/// A request was answered somewhere else (e.g. in the TUI), drop it from the queue
+pub fn permission_resolved(
+    app_handle: &tauri::AppHandle,
+    request_id: &str,
+    reply: Option<opentray_client::PermissionReply>,
+) {
+    if let Some(data) = permissions::remove(request_id) {
+        let mut record = AuditRecord::new(&data, AuditDecision::ResolvedElsewhere, false);
+        record.reply = reply.map(AuditDecision::from);
+        audit::record(app_handle, &record);
+        permissions_changed(app_handle);
+        present_permission_popup(app_handle);
     }
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        if let Some(window) = app_handle.get_webview_window("permission") {
-            // Position the popup near the tray icon
-            position_permission_popup(&app_handle);
+/// Show the popup with the oldest pending request, or hide it when the queue is empty
+pub fn present_permission_popup(app_handle: &tauri::AppHandle) {
+    let Some(window) = app_handle.get_webview_window("permission") else {
+        return;
+    };
 
-            // Show the window
-            let _ = window.show();
-            let _ = window.set_focus();
+    let Some(data) = permissions::front() else {
+        let _ = window.hide();
+        return;
+    };
 
-            // Emit the permission data to the window
-            let _ = window.emit("permission-request", &data);
-        }
+    #[cfg(target_os = "macos")]
+    {
+        // Position the popup near the tray icon
+        position_permission_popup(app_handle);
     }
 
     #[cfg(not(target_os = "macos"))]
     {
-        if let Some(window) = app_handle.get_webview_window("permission") {
-            let _ = window.center();
-            let _ = window.show();
-            let _ = window.set_focus();
-            let _ = window.emit("permission-request", &data);
-        }
+        let _ = window.center();
     }
 
-    Ok(())
+    let _ = window.show();
+    let _ = window.set_focus();
+
+    // Emit the permission data to the window
+    let _ = window.emit("permission-request", &data);
+}
+
+/// Tell every window how many requests are pending and badge the tray
+fn permissions_changed(app_handle: &tauri::AppHandle) {
+    let count = permissions::len();
+    let _ = app_handle.emit("permissions-changed", count);
+
+    crate::tray_icon::set_pending_permissions(app_handle, count);
+    crate::tray_menu::rebuild(app_handle);
 }
 
 #[tauri::command]
 pub fn hide_permission_popup(app_handle: tauri::AppHandle) -> Result<(), String> {
-    // Clear the pending permission
-    {
-        let mut pending = PENDING_PERMISSION.lock().map_err(|e| e.to_string())?;
-        *pending = None;
-    }
-
     if let Some(window) = app_handle.get_webview_window("permission") {
         let _ = window.hide();
     }
@@ -303,10 +427,155 @@ pub fn hide_permission_popup(app_handle: tauri::AppHandle) -> Result<(), String>
     Ok(())
 }
 
+/// The request currently shown in the popup
+#[tauri::command]
+pub fn get_pending_permission() -> Result<Option<PermissionData>, String> {
+    Ok(permissions::front())
+}
+
+#[tauri::command]
+pub fn list_pending_permissions() -> Result<Vec<PermissionData>, String> {
+    Ok(permissions::list())
+}
+
+/// Drop a request without answering it and move on to the next one
+#[tauri::command]
+pub fn dismiss_permission(app_handle: tauri::AppHandle, id: String) -> Result<(), String> {
+    let data =
+        permissions::remove(&id).ok_or_else(|| format!("No pending permission with id {}", id))?;
+    audit::record(
+        &app_handle,
+        &AuditRecord::new(&data, AuditDecision::Dismissed, false),
+    );
+    permissions_changed(&app_handle);
+    present_permission_popup(&app_handle);
+    Ok(())
+}
+
+/// Error returned by `permission_reply`, serialized as `{ kind, message }`
+#[derive(serde::Serialize, Debug)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum PermissionReplyError {
+    /// The OpenCode instance that asked is no longer running
+    InstanceGone(String),
+    /// The instance couldn't be reached this time; the request stays queued
+    Unreachable(String),
+    /// The instance answered with an error
+    Rejected(String),
+}
+
+impl std::fmt::Display for PermissionReplyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionReplyError::InstanceGone(url) => write!(f, "Instance {} is gone", url),
+            PermissionReplyError::Unreachable(msg) => write!(f, "Instance unreachable: {}", msg),
+            PermissionReplyError::Rejected(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// Answer a permission request on the instance that asked for it
 #[tauri::command]
-pub fn get_pending_permission() -> Result<Option<serde_json::Value>, String> {
-    let pending = PENDING_PERMISSION.lock().map_err(|e| e.to_string())?;
-    Ok(pending.clone())
+pub async fn permission_reply(
+    app_handle: tauri::AppHandle,
+    instance_url: String,
+    request_id: String,
+    reply: opentray_client::PermissionReply,
+) -> Result<(), PermissionReplyError> {
+    answer_permission(&app_handle, instance_url, &request_id, reply, false).await
+}
+
+/// Post a reply to the owning instance, then advance the popup to the next request
+/// Requests whose instance is no longer discovered are dropped as they can never be
+/// answered; transport errors keep them queued so the reply can be retried
+pub async fn answer_permission(
+    app_handle: &tauri::AppHandle,
+    instance_url: String,
+    request_id: &str,
+    reply: opentray_client::PermissionReply,
+    automatic: bool,
+) -> Result<(), PermissionReplyError> {
+    // Prefer the instance stored with the queued request over the caller's value
+    let pending = permissions::get(request_id);
+    let instance_url = pending
+        .as_ref()
+        .map(|p| p.instance_url.clone())
+        .unwrap_or(instance_url);
+
+    let result = match crate::discovery::instances()
+        .into_iter()
+        .find(|i| i.url == instance_url)
+    {
+        Some(instance) => opentray_client::Client::new(&instance.url)
+            .directory(&instance.directory)
+            .reply_permission(request_id, reply)
+            .await
+            .map_err(|e| match e {
+                opentray_client::Error::Http(e) => PermissionReplyError::Unreachable(e.to_string()),
+                e => PermissionReplyError::Rejected(e.to_string()),
+            }),
+        None => Err(PermissionReplyError::InstanceGone(instance_url.clone())),
+    };
+
+    if let Some(record) = pending
+        .as_ref()
+        .and_then(|data| audit_record(data, reply, automatic, &result))
+    {
+        audit::record(app_handle, &record);
+    }
+
+    if settles(&result) {
+        permissions::remove(request_id);
+        permissions_changed(app_handle);
+        present_permission_popup(app_handle);
+    }
+
+    result
+}
+
+/// Answer a queued request in the background, unless it was answered elsewhere meanwhile
+///
+/// For the tray menu and notification buttons, which have nowhere to show an error.
+pub fn reply_queued(
+    app_handle: &tauri::AppHandle,
+    request_id: &str,
+    reply: opentray_client::PermissionReply,
+) {
+    let Some(pending) = permissions::get(request_id) else {
+        return;
+    };
+    let app_handle = app_handle.clone();
+    let request_id = request_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) =
+            answer_permission(&app_handle, pending.instance_url, &request_id, reply, false).await
+        {
+            log::warn!("failed to answer {}: {}", request_id, e);
+        }
+    });
+}
+
+/// Whether a reply outcome takes the request off the queue
+fn settles(result: &Result<(), PermissionReplyError>) -> bool {
+    matches!(result, Ok(()) | Err(PermissionReplyError::InstanceGone(_)))
+}
+
+/// The audit log entry for a reply outcome, if it gets one
+///
+/// Every outcome that drops the request is logged, so none vanish from the record.
+/// Failures that keep it queued are logged for policy answers only, as nobody saw them.
+fn audit_record(
+    data: &PermissionData,
+    reply: opentray_client::PermissionReply,
+    automatic: bool,
+    result: &Result<(), PermissionReplyError>,
+) -> Option<AuditRecord> {
+    if !settles(result) && !automatic {
+        return None;
+    }
+    let mut record = AuditRecord::new(data, reply.into(), automatic);
+    record.error = result.as_ref().err().map(|e| e.to_string());
+    Some(record)
 }
 
 // --------------------------------------------
@@ -320,135 +589,68 @@ pub struct ProjectFile {
     pub is_dir: bool,
 }
 
-/// List project files with optional search query
-/// Uses gitignore rules and common ignore patterns
+/// List project files whose path contains `query`, honoring gitignore rules
+///
+/// Answered from an in-memory index per directory that a file watcher keeps
+/// current; only the first query for a directory walks the disk.
 #[tauri::command]
-pub fn list_project_files(directory: String, query: String, limit: usize) -> Result<Vec<ProjectFile>, String> {
-    use std::collections::HashSet;
-    
-    let root = PathBuf::from(&directory);
-    if !root.exists() || !root.is_dir() {
-        return Err("Directory does not exist".to_string());
-    }
-    
-    let query_lower = query.to_lowercase();
-    let mut files: Vec<ProjectFile> = Vec::new();
-    let mut visited: HashSet<PathBuf> = HashSet::new();
-    
-    // Common directories to ignore
-    let ignore_dirs: HashSet<&str> = [
-        "node_modules", ".git", ".svn", ".hg", "target", "dist", "build",
-        ".next", ".nuxt", ".output", "__pycache__", ".pytest_cache",
-        "venv", ".venv", "env", ".env", ".idea", ".vscode",
-        "coverage", ".nyc_output", ".cache", ".parcel-cache",
-        "vendor", "bower_components", ".gradle", ".m2",
-    ].iter().cloned().collect();
-    
-    // Common files to ignore
-    let ignore_files: HashSet<&str> = [
-        ".DS_Store", "Thumbs.db", ".gitignore", ".gitattributes",
-        "package-lock.json", "yarn.lock", "pnpm-lock.yaml",
-        "Cargo.lock", "poetry.lock", "composer.lock",
-    ].iter().cloned().collect();
-    
-    #[allow(clippy::too_many_arguments)]
-    fn walk_dir(
-        dir: &PathBuf,
-        root: &PathBuf,
-        query: &str,
-        files: &mut Vec<ProjectFile>,
-        visited: &mut HashSet<PathBuf>,
-        ignore_dirs: &HashSet<&str>,
-        ignore_files: &HashSet<&str>,
-        limit: usize,
-        depth: usize,
-    ) {
-        if files.len() >= limit || depth > 10 {
-            return;
-        }
-        
-        let entries = match std::fs::read_dir(dir) {
-            Ok(e) => e,
-            Err(_) => return,
-        };
-        
-        for entry in entries.flatten() {
-            if files.len() >= limit {
-                return;
-            }
-            
-            let path = entry.path();
-            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
-            
-            if visited.contains(&canonical) {
-                continue;
-            }
-            visited.insert(canonical);
-            
-            let file_name = match entry.file_name().into_string() {
-                Ok(n) => n,
-                Err(_) => continue,
-            };
-            
-            // Skip hidden files/dirs (except we want to show them if explicitly searched)
-            if file_name.starts_with('.') && !query.starts_with('.') {
-                continue;
-            }
-            
-            let is_dir = path.is_dir();
-            
-            // Skip ignored directories
-            if is_dir && ignore_dirs.contains(file_name.as_str()) {
-                continue;
-            }
-            
-            // Skip ignored files
-            if !is_dir && ignore_files.contains(file_name.as_str()) {
-                continue;
-            }
-            
-            // Get relative path from project root
-            let relative_path = path.strip_prefix(root)
-                .map(|p| p.to_string_lossy().to_string().replace('\\', "/"))
-                .unwrap_or_else(|_| file_name.clone());
-            
-            // Check if matches query (fuzzy match on path or name)
-            let matches = query.is_empty() || 
-                file_name.to_lowercase().contains(query) ||
-                relative_path.to_lowercase().contains(query);
-            
-            if matches && !is_dir {
-                files.push(ProjectFile {
-                    path: relative_path,
-                    name: file_name.clone(),
-                    is_dir,
-                });
-            }
-            
-            // Recurse into directories
-            if is_dir {
-                walk_dir(
-                    &path, root, query, files, visited, 
-                    ignore_dirs, ignore_files, limit, depth + 1
-                );
-            }
+pub async fn list_project_files(
+    app_handle: tauri::AppHandle,
+    directory: String,
+    query: String,
+    limit: usize,
+) -> Result<Vec<ProjectFile>, String> {
+    let directory = sandbox::resolve(&app_handle, &directory).map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::file_index::search(&directory, &query, limit)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentray_client::PermissionReply;
+
+    fn data() -> PermissionData {
+        PermissionData {
+            id: "per_1".to_string(),
+            request: serde_json::json!({ "id": "per_1", "permission": "bash" }),
+            session_title: "Session".to_string(),
+            instance_url: "http://127.0.0.1:4096".to_string(),
         }
     }
-    
-    walk_dir(
-        &root, &root, &query_lower, &mut files, &mut visited,
-        &ignore_dirs, &ignore_files, limit, 0
-    );
-    
-    // Sort by path length (shorter = more relevant) then alphabetically
-    files.sort_by(|a, b| {
-        let len_cmp = a.path.len().cmp(&b.path.len());
-        if len_cmp == std::cmp::Ordering::Equal {
-            a.path.to_lowercase().cmp(&b.path.to_lowercase())
-        } else {
-            len_cmp
+
+    fn error(automatic: bool, result: Result<(), PermissionReplyError>) -> Option<Option<String>> {
+        audit_record(&data(), PermissionReply::Once, automatic, &result).map(|r| r.error)
+    }
+
+    #[test]
+    fn logs_every_outcome_that_drops_the_request() {
+        let gone = || Err(PermissionReplyError::InstanceGone(data().instance_url));
+        for automatic in [false, true] {
+            assert_eq!(error(automatic, Ok(())), Some(None));
+            assert_eq!(
+                error(automatic, gone()),
+                Some(Some("Instance http://127.0.0.1:4096 is gone".to_string()))
+            );
         }
-    });
-    
-    Ok(files)
+    }
+
+    #[test]
+    fn logs_failures_that_keep_the_request_for_policy_answers_only() {
+        let unreachable = || Err(PermissionReplyError::Unreachable("timed out".to_string()));
+        let rejected = || Err(PermissionReplyError::Rejected("bad request".to_string()));
+        assert_eq!(error(false, unreachable()), None);
+        assert_eq!(error(false, rejected()), None);
+        assert_eq!(
+            error(true, unreachable()),
+            Some(Some("Instance unreachable: timed out".to_string()))
+        );
+        assert_eq!(
+            error(true, rejected()),
+            Some(Some("bad request".to_string()))
+        );
+    }
 }
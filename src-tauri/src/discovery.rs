@@ -0,0 +1,236 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures_util::stream::{self, StreamExt};
+use opentray_client::Client;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_http::reqwest;
+
+pub const DEFAULT_PORT_START: u16 = 4096;
+pub const DEFAULT_PORT_COUNT: u16 = 10;
+
+// Upper bound on the scanned range, whatever the settings file says
+pub const MAX_PORT_COUNT: u16 = 1000;
+
+// How often the background scan runs and how long a single probe may take
+const SCAN_INTERVAL: Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+// Probes in flight at once, so a wide range doesn't open hundreds of connections
+const MAX_CONCURRENT_PROBES: usize = 32;
+
+// Directory shown for a server that reports neither its path nor a project
+const UNKNOWN_DIRECTORY: &str = "Unknown";
+
+// Last scan result, shared with commands and the tray
+static INSTANCES: Mutex<Vec<OpenCodeInstance>> = Mutex::new(Vec::new());
+
+// Port range to scan: (first port, number of ports)
+static PORT_RANGE: Mutex<(u16, u16)> = Mutex::new((DEFAULT_PORT_START, DEFAULT_PORT_COUNT));
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static SCANNED: AtomicBool = AtomicBool::new(false);
+
+/// A running OpenCode server found on localhost
+/// Mirrors `OpenCodeInstance` in src/lib/types.ts
+#[derive(serde::Serialize, Clone, PartialEq, Debug)]
+pub struct OpenCodeInstance {
+    pub url: String,
+    pub port: u16,
+    pub directory: String,
+    pub connected: bool,
+    pub version: Option<String>,
+}
+
+/// Start the background discovery loop (only once per process)
+pub fn start(app_handle: &AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            rescan(&handle).await;
+            tokio::time::sleep(SCAN_INTERVAL).await;
+        }
+    });
+}
+
+/// Change the scanned port range, e.g. after settings were saved
+/// The count is clamped to `1..=MAX_PORT_COUNT`
+pub fn set_port_range(start: u16, count: u16) {
+    if let Ok(mut range) = PORT_RANGE.lock() {
+        *range = clamp_range(start, count);
+    }
+}
+
+fn clamp_range(start: u16, count: u16) -> (u16, u16) {
+    (start, count.clamp(1, MAX_PORT_COUNT))
+}
+
+/// Ports in the range, stopping at the last valid port
+fn ports(start: u16, count: u16) -> impl Iterator<Item = u16> {
+    (0..count).map_while(move |offset| start.checked_add(offset))
+}
+
+/// Snapshot of the instances found by the last scan
+pub fn instances() -> Vec<OpenCodeInstance> {
+    INSTANCES.lock().map(|i| i.clone()).unwrap_or_default()
+}
+
+/// Scan the port range once and emit `instances-changed` if the result differs
+pub async fn rescan(app_handle: &AppHandle) -> Vec<OpenCodeInstance> {
+    let found = scan().await;
+    SCANNED.store(true, Ordering::SeqCst);
+
+    let changed = INSTANCES
+        .lock()
+        .map(|mut current| replace_if_changed(&mut current, &found))
+        .unwrap_or(false);
+
+    if changed {
+        crate::events::sync(app_handle, &found);
+        crate::tray_menu::refresh(app_handle);
+        let _ = app_handle.emit("instances-changed", &found);
+    }
+
+    found
+}
+
+/// Store a scan result, returns whether it differs from the previous one
+fn replace_if_changed(current: &mut Vec<OpenCodeInstance>, found: &[OpenCodeInstance]) -> bool {
+    if current.as_slice() == found {
+        return false;
+    }
+    *current = found.to_vec();
+    true
+}
+
+async fn scan() -> Vec<OpenCodeInstance> {
+    let (start, count) = PORT_RANGE
+        .lock()
+        .map(|r| *r)
+        .unwrap_or((DEFAULT_PORT_START, DEFAULT_PORT_COUNT));
+
+    let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+
+    // Probe a bounded number of ports at a time
+    let mut instances: Vec<OpenCodeInstance> = stream::iter(ports(start, count))
+        .map(|port| probe(&client, port))
+        .buffer_unordered(MAX_CONCURRENT_PROBES)
+        .filter_map(|found| async move { found })
+        .collect()
+        .await;
+
+    // Probes finish in any order, keep the list stable between scans
+    instances.sort_by_key(|instance| instance.port);
+    instances
+}
+
+/// Check a single port for an OpenCode server
+async fn probe(http: &reqwest::Client, port: u16) -> Option<OpenCodeInstance> {
+    let url = format!("http://127.0.0.1:{}", port);
+    let client = Client::with_http(http.clone(), url.clone());
+
+    let health = client.health().await.ok()?;
+    if !health.healthy {
+        return None;
+    }
+
+    let path = client.path().await.ok().map(|path| path.directory);
+    // Fall back to the current project for older servers
+    let project = match path {
+        Some(_) => None,
+        None => client.current_project().await.ok().map(|p| p.path),
+    };
+
+    Some(OpenCodeInstance {
+        url,
+        port,
+        directory: directory(path, project),
+        connected: true,
+        version: health.version,
+    })
+}
+
+/// The reported path, else the current project's, else `UNKNOWN_DIRECTORY`
+fn directory(path: Option<String>, project: Option<String>) -> String {
+    path.or(project)
+        .unwrap_or_else(|| UNKNOWN_DIRECTORY.to_string())
+}
+
+// --------------------------------------------
+// Commands
+// --------------------------------------------
+
+/// List known OpenCode instances
+/// Runs a scan first if the background loop has not completed one yet
+#[tauri::command]
+pub async fn list_instances(app_handle: AppHandle) -> Result<Vec<OpenCodeInstance>, String> {
+    if !SCANNED.load(Ordering::SeqCst) {
+        return Ok(rescan(&app_handle).await);
+    }
+    Ok(instances())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(port: u16, version: Option<&str>) -> OpenCodeInstance {
+        OpenCodeInstance {
+            url: format!("http://127.0.0.1:{}", port),
+            port,
+            directory: "/proj".to_string(),
+            connected: true,
+            version: version.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn clamps_the_port_count() {
+        assert_eq!(clamp_range(4096, 0), (4096, 1));
+        assert_eq!(clamp_range(4096, 10), (4096, 10));
+        assert_eq!(clamp_range(4096, u16::MAX), (4096, MAX_PORT_COUNT));
+    }
+
+    #[test]
+    fn stops_at_the_last_port() {
+        assert_eq!(ports(4096, 3).collect::<Vec<_>>(), vec![4096, 4097, 4098]);
+        assert_eq!(
+            ports(u16::MAX - 1, 5).collect::<Vec<_>>(),
+            vec![u16::MAX - 1, u16::MAX]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_project_then_unknown() {
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(directory(some("/path"), some("/project")), "/path");
+        assert_eq!(directory(None, some("/project")), "/project");
+        assert_eq!(directory(None, None), UNKNOWN_DIRECTORY);
+    }
+
+    #[test]
+    fn reports_only_real_changes() {
+        let mut current = Vec::new();
+        assert!(!replace_if_changed(&mut current, &[]));
+
+        let found = vec![instance(4096, Some("1.0")), instance(4097, None)];
+        assert!(replace_if_changed(&mut current, &found));
+        assert_eq!(current, found);
+        assert!(!replace_if_changed(&mut current, &found));
+
+        // Any field counts, not just the set of ports
+        let upgraded = vec![instance(4096, Some("1.1")), instance(4097, None)];
+        assert!(replace_if_changed(&mut current, &upgraded));
+        assert!(replace_if_changed(&mut current, &upgraded[..1]));
+        assert!(replace_if_changed(&mut current, &[]));
+        assert!(current.is_empty());
+    }
+}
@@ -0,0 +1,512 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_http::reqwest;
+
+use crate::discovery::OpenCodeInstance;
+
+// Reconnect backoff bounds
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+// One running subscription per instance URL
+static SUBSCRIPTIONS: Mutex<Option<HashMap<String, JoinHandle<()>>>> = Mutex::new(None);
+
+// --------------------------------------------
+// Event Types
+// --------------------------------------------
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStatus {
+    Idle,
+    Busy,
+    Retry,
+}
+
+/// Session status as sent by OpenCode, either `"busy"` or `{ "type": "busy", ... }`
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum StatusValue {
+    Plain(SessionStatus),
+    Tagged {
+        #[serde(rename = "type")]
+        kind: SessionStatus,
+    },
+}
+
+impl StatusValue {
+    pub fn status(&self) -> SessionStatus {
+        match self {
+            StatusValue::Plain(status) => *status,
+            StatusValue::Tagged { kind } => *kind,
+        }
+    }
+}
+
+/// Permission request as sent with `permission.asked`
+/// Mirrors `PermissionRequest` in src/lib/types.ts
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct PermissionRequest {
+    pub id: String,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    pub permission: String,
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/// Events published on OpenCode's `/global/event` stream
+/// Payloads we don't inspect are kept as raw JSON so the frontend sees them unchanged,
+/// event types not listed here are dropped
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(tag = "type", content = "properties")]
+pub enum OpenCodeEvent {
+    #[serde(rename = "server.connected")]
+    ServerConnected {},
+    #[serde(rename = "server.heartbeat")]
+    ServerHeartbeat {},
+    #[serde(rename = "session.updated")]
+    SessionUpdated(serde_json::Value),
+    #[serde(rename = "session.deleted")]
+    SessionDeleted { info: serde_json::Value },
+    #[serde(rename = "session.status")]
+    SessionStatus {
+        #[serde(rename = "sessionID")]
+        session_id: String,
+        status: StatusValue,
+    },
+    #[serde(rename = "session.idle")]
+    SessionIdle {
+        #[serde(rename = "sessionID")]
+        session_id: String,
+    },
+    #[serde(rename = "session.error")]
+    SessionError {
+        #[serde(rename = "sessionID", default)]
+        session_id: Option<String>,
+        #[serde(default)]
+        error: serde_json::Value,
+    },
+    #[serde(rename = "message.updated")]
+    MessageUpdated { info: serde_json::Value },
+    #[serde(rename = "message.part.updated")]
+    MessagePartUpdated {
+        part: serde_json::Value,
+        #[serde(default)]
+        delta: Option<String>,
+    },
+    #[serde(rename = "message.part.removed")]
+    MessagePartRemoved {
+        #[serde(rename = "sessionID")]
+        session_id: String,
+        #[serde(rename = "messageID")]
+        message_id: String,
+        #[serde(rename = "partID")]
+        part_id: String,
+    },
+    #[serde(rename = "permission.asked")]
+    PermissionAsked(PermissionRequest),
+    #[serde(rename = "permission.replied")]
+    PermissionReplied {
+        #[serde(rename = "sessionID")]
+        session_id: String,
+        #[serde(alias = "requestID", alias = "permissionID")]
+        id: String,
+        #[serde(default, alias = "response")]
+        reply: Option<opentray_client::PermissionReply>,
+    },
+    #[serde(rename = "todo.updated")]
+    TodoUpdated {
+        #[serde(rename = "sessionID")]
+        session_id: String,
+        #[serde(default)]
+        todos: Vec<serde_json::Value>,
+    },
+}
+
+/// `/global/event` wraps each event with the directory it belongs to
+#[derive(serde::Deserialize)]
+struct GlobalEvent {
+    #[serde(default)]
+    directory: Option<String>,
+    payload: OpenCodeEvent,
+}
+
+/// Event re-emitted to the webview as `opencode-event`
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct InstanceEvent {
+    #[serde(rename = "instanceUrl")]
+    pub instance_url: String,
+    pub directory: Option<String>,
+    pub event: OpenCodeEvent,
+}
+
+fn decode_event(data: &str) -> Option<(Option<String>, OpenCodeEvent)> {
+    if let Ok(global) = serde_json::from_str::<GlobalEvent>(data) {
+        return Some((global.directory, global.payload));
+    }
+    serde_json::from_str::<OpenCodeEvent>(data)
+        .ok()
+        .map(|event| (None, event))
+}
+
+// --------------------------------------------
+// SSE Parsing
+// --------------------------------------------
+
+#[derive(Default, Debug)]
+struct SseMessage {
+    id: Option<String>,
+    data: String,
+    retry: Option<u64>,
+}
+
+/// Incremental parser for `text/event-stream` bodies
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+    current: SseMessage,
+    has_data: bool,
+}
+
+impl SseParser {
+    fn feed(&mut self, chunk: &[u8]) -> Vec<SseMessage> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut messages = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            // Decode whole lines only, so multi-byte characters split across chunks survive
+            let bytes: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&bytes);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            // A blank line dispatches the current message
+            if line.is_empty() {
+                let message = std::mem::take(&mut self.current);
+                if self.has_data || message.id.is_some() || message.retry.is_some() {
+                    messages.push(message);
+                }
+                self.has_data = false;
+                continue;
+            }
+
+            // Comment line
+            if line.starts_with(':') {
+                continue;
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+
+            match field {
+                "data" => {
+                    if self.has_data {
+                        self.current.data.push('\n');
+                    }
+                    self.current.data.push_str(value);
+                    self.has_data = true;
+                }
+                "id" => self.current.id = Some(value.to_string()),
+                "retry" => self.current.retry = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        messages
+    }
+}
+
+// --------------------------------------------
+// Subscriptions
+// --------------------------------------------
+
+/// Start subscriptions for new instances and stop those that disappeared
+pub fn sync(app_handle: &AppHandle, instances: &[OpenCodeInstance]) {
+    let Ok(mut guard) = SUBSCRIPTIONS.lock() else {
+        return;
+    };
+    let subscriptions = guard.get_or_insert_with(HashMap::new);
+
+    subscriptions.retain(|url, task| {
+        let alive = instances.iter().any(|i| &i.url == url);
+        if !alive {
+            task.abort();
+            crate::tray_icon::forget_instance(app_handle, url);
+            crate::notifications::forget_instance(url);
+        }
+        alive
+    });
+
+    for instance in instances {
+        if subscriptions.contains_key(&instance.url) {
+            continue;
+        }
+        let handle = app_handle.clone();
+        let url = instance.url.clone();
+        let task = tauri::async_runtime::spawn(async move { run(handle, url).await });
+        subscriptions.insert(instance.url.clone(), task);
+    }
+}
+
+/// Keep a connection to one instance alive, reconnecting with backoff
+async fn run(app_handle: AppHandle, instance_url: String) {
+    let client = match reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+    {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+
+    let mut last_event_id: Option<String> = None;
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        match stream(&app_handle, &client, &instance_url, &mut last_event_id, &mut backoff).await {
+            Ok(()) => log::debug!("stream from {} closed", instance_url),
+            Err(e) => log::warn!("stream from {} failed: {}", instance_url, e),
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Read one connection until it ends
+async fn stream(
+    app_handle: &AppHandle,
+    client: &reqwest::Client,
+    instance_url: &str,
+    last_event_id: &mut Option<String>,
+    backoff: &mut Duration,
+) -> Result<(), reqwest::Error> {
+    let mut request = client
+        .get(format!("{}/global/event", instance_url))
+        .header("Accept", "text/event-stream");
+    if let Some(id) = last_event_id.as_deref() {
+        request = request.header("Last-Event-ID", id);
+    }
+
+    let mut response = request.send().await?.error_for_status()?;
+
+    // Connected, so the next failure starts over with a short delay
+    *backoff = INITIAL_BACKOFF;
+
+    let mut parser = SseParser::default();
+    while let Some(chunk) = response.chunk().await? {
+        for message in parser.feed(&chunk) {
+            if let Some(retry) = message.retry {
+                *backoff = retry_backoff(retry);
+            }
+            if let Some(id) = message.id {
+                *last_event_id = Some(id);
+            }
+            if message.data.is_empty() {
+                continue;
+            }
+            if let Some((directory, event)) = decode_event(&message.data) {
+                dispatch(
+                    app_handle,
+                    InstanceEvent {
+                        instance_url: instance_url.to_string(),
+                        directory,
+                        event,
+                    },
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reconnect delay for a server-sent `retry`, kept within our own bounds so a
+/// `retry: 0` can't turn reconnecting into a busy loop
+fn retry_backoff(retry: u64) -> Duration {
+    Duration::from_millis(retry).clamp(INITIAL_BACKOFF, MAX_BACKOFF)
+}
+
+/// Forward a decoded event to the backend subsystems and the webview
+fn dispatch(app_handle: &AppHandle, event: InstanceEvent) {
+    match &event.event {
+        OpenCodeEvent::ServerHeartbeat {} => return,
+        OpenCodeEvent::PermissionAsked(request) => {
+            receive_permission(app_handle, &event.instance_url, request.clone());
+        }
+        OpenCodeEvent::PermissionReplied { id, reply, .. } => {
+            crate::command::permission_resolved(app_handle, id, *reply);
+        }
+        OpenCodeEvent::SessionUpdated(_) => {
+            crate::tray_menu::refresh(app_handle);
+        }
+        OpenCodeEvent::SessionDeleted { info } => {
+            if let Some(session_id) = info.get("id").and_then(|id| id.as_str()) {
+                crate::notifications::forget_session(&event.instance_url, session_id);
+            }
+            crate::tray_menu::refresh(app_handle);
+        }
+        OpenCodeEvent::SessionStatus { session_id, status } => {
+            if status.status() == SessionStatus::Idle {
+                finished(app_handle, &event.instance_url, session_id);
+            }
+            crate::tray_icon::session_status(
+                app_handle,
+                &event.instance_url,
+                session_id,
+                status.status(),
+            );
+            crate::tray_menu::refresh(app_handle);
+        }
+        OpenCodeEvent::SessionIdle { session_id } => {
+            finished(app_handle, &event.instance_url, session_id);
+            crate::tray_icon::session_status(
+                app_handle,
+                &event.instance_url,
+                session_id,
+                SessionStatus::Idle,
+            );
+            crate::tray_menu::refresh(app_handle);
+        }
+        // Aborting a session reports an error too, that's not worth flagging
+        OpenCodeEvent::SessionError {
+            session_id: Some(session_id),
+            error,
+        } if error.get("name").and_then(|n| n.as_str()) != Some("MessageAbortedError") => {
+            crate::tray_icon::session_error(app_handle, &event.instance_url, session_id);
+            crate::notifications::session_error(app_handle, &event.instance_url, session_id, error);
+        }
+        OpenCodeEvent::TodoUpdated { session_id, todos } => {
+            crate::notifications::todos_updated(app_handle, &event.instance_url, session_id, todos);
+        }
+        _ => {}
+    }
+
+    let _ = app_handle.emit("opencode-event", &event);
+}
+
+/// Notify when a session that was working goes idle
+///
+/// Both `session.status` and `session.idle` report it, only the first one counts.
+fn finished(app_handle: &AppHandle, instance_url: &str, session_id: &str) {
+    if crate::tray_icon::is_busy(instance_url, session_id) {
+        crate::notifications::session_done(app_handle, instance_url, session_id);
+    }
+}
+
+/// Queue a permission request seen on the stream, looking up its session title first
+fn receive_permission(app_handle: &AppHandle, instance_url: &str, request: PermissionRequest) {
+    let handle = app_handle.clone();
+    let instance_url = instance_url.to_string();
+    tauri::async_runtime::spawn(async move {
+        let directory = crate::discovery::instances()
+            .into_iter()
+            .find(|i| i.url == instance_url)
+            .map(|i| i.directory);
+
+        let mut client = opentray_client::Client::new(&instance_url);
+        if let Some(directory) = directory {
+            client = client.directory(directory);
+        }
+        let session_title = client
+            .session(&request.session_id)
+            .await
+            .map(|s| s.title)
+            .unwrap_or_else(|_| "Unknown session".to_string());
+
+        let data = crate::permissions::PermissionData {
+            id: request.id.clone(),
+            request: serde_json::to_value(&request).unwrap_or_default(),
+            session_title,
+            instance_url: instance_url.clone(),
+        };
+        if let Err(e) = crate::command::receive_permission(&handle, data) {
+            log::warn!("dropping permission request from {}: {}", instance_url, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(chunks: &[&str]) -> Vec<SseMessage> {
+        let mut parser = SseParser::default();
+        chunks
+            .iter()
+            .flat_map(|chunk| parser.feed(chunk.as_bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn joins_multi_line_data() {
+        let messages = parse(&["data: first\ndata: second\ndata:third\n\n"]);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].data, "first\nsecond\nthird");
+    }
+
+    #[test]
+    fn skips_comments() {
+        let messages = parse(&[": keep-alive\n\n", "data: a\n: in between\ndata: b\n\n"]);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].data, "a\nb");
+    }
+
+    #[test]
+    fn reads_id_and_retry_and_ignores_event_names() {
+        let messages = parse(&[
+            "event: message\nid: 42\nretry: 3000\ndata: {}\n\n",
+            "id: 43\n\n",
+        ]);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].id.as_deref(), Some("42"));
+        assert_eq!(messages[0].retry, Some(3000));
+        assert_eq!(messages[0].data, "{}");
+        // An id on its own still moves the resume point
+        assert_eq!(messages[1].id.as_deref(), Some("43"));
+        assert_eq!(messages[1].data, "");
+    }
+
+    #[test]
+    fn clamps_server_retry_to_the_backoff_bounds() {
+        let messages = parse(&["retry: 0\n\n"]);
+        assert_eq!(messages[0].retry, Some(0));
+        assert_eq!(retry_backoff(0), INITIAL_BACKOFF);
+        assert_eq!(retry_backoff(5000), Duration::from_secs(5));
+        assert_eq!(retry_backoff(u64::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn waits_for_lines_split_across_chunks() {
+        let messages = parse(&["da", "ta: hel", "lo\n", "\n"]);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].data, "hello");
+
+        // A multi-byte character cut in half
+        let bytes = "data: é\n\n".as_bytes();
+        let mut parser = SseParser::default();
+        assert!(parser.feed(&bytes[..7]).is_empty());
+        assert_eq!(parser.feed(&bytes[7..])[0].data, "é");
+    }
+
+    #[test]
+    fn accepts_crlf_line_endings() {
+        let messages = parse(&["id: 1\r\ndata: a\r\n", "data: b\r\n\r\n"]);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id.as_deref(), Some("1"));
+        assert_eq!(messages[0].data, "a\nb");
+    }
+
+    #[test]
+    fn blank_lines_alone_dispatch_nothing() {
+        assert!(parse(&["\n\n\r\n"]).is_empty());
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock, Weak};
+use std::time::{Duration, Instant};
+
+use ignore::WalkBuilder;
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+
+use crate::command::ProjectFile;
+
+// Project directories kept indexed at once, least recently queried go first
+const MAX_INDEXES: usize = 8;
+
+// Stop indexing huge trees past this many files
+const MAX_FILES: usize = 200_000;
+
+// Builds and checkouts touch many files at once, reindex once they settle
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+// Version control internals, never listed even though they aren't gitignored
+const ALWAYS_SKIPPED: &[&str] = &[".git", ".hg", ".svn"];
+
+// Files that change what the walk skips
+const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore"];
+
+static INDEXES: LazyLock<Mutex<HashMap<PathBuf, Cached>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+struct Cached {
+    /// Filled by the first query; later ones for the same root wait for it
+    index: Arc<OnceLock<Arc<Index>>>,
+    last_used: Instant,
+}
+
+/// Files of one project directory, rebuilt by its watcher
+struct Index {
+    root: PathBuf,
+    snapshot: RwLock<Snapshot>,
+    /// Dropping the index stops the watcher
+    watcher: Mutex<Option<Debouncer<RecommendedWatcher>>>,
+    /// Directories the watcher has been told about
+    watched: Mutex<HashSet<PathBuf>>,
+}
+
+#[derive(Default)]
+struct Snapshot {
+    files: Vec<IndexedFile>,
+    /// Absolute paths of `files`
+    listed: HashSet<PathBuf>,
+    /// Directories the walk entered, the only ones watched
+    dirs: HashSet<PathBuf>,
+}
+
+struct IndexedFile {
+    /// Relative to the root, with `/` separators
+    path: String,
+    name: String,
+    lowercase_path: String,
+    /// The file or one of its directories starts with a dot
+    hidden: bool,
+}
+
+/// Walker honoring `.gitignore`, `.ignore`, `.git/info/exclude` and the
+/// global excludes file
+fn walker(root: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(false)
+        .parents(true)
+        .ignore(true)
+        .git_ignore(true)
+        .git_global(true)
+        .git_exclude(true)
+        // Projects that aren't repositories still get their .gitignore applied
+        .require_git(false)
+        .follow_links(false)
+        .filter_entry(|entry| {
+            !(entry.file_type().is_some_and(|t| t.is_dir())
+                && entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| ALWAYS_SKIPPED.contains(&name)))
+        });
+    builder
+}
+
+fn walk(root: &Path) -> Snapshot {
+    let mut snapshot = Snapshot::default();
+    snapshot.dirs.insert(root.to_path_buf());
+    for entry in walker(root).build().flatten() {
+        let Some(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            snapshot.dirs.insert(entry.path().to_path_buf());
+            continue;
+        }
+        if !file_type.is_file() {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+
+        let path = relative.to_string_lossy().replace('\\', "/");
+        snapshot.files.push(IndexedFile {
+            name: entry.file_name().to_string_lossy().to_string(),
+            lowercase_path: path.to_lowercase(),
+            hidden: path.split('/').any(|part| part.starts_with('.')),
+            path,
+        });
+        snapshot.listed.insert(entry.path().to_path_buf());
+        if snapshot.files.len() >= MAX_FILES {
+            log::warn!(
+                "{} has more than {} files, the rest aren't indexed",
+                root.display(),
+                MAX_FILES
+            );
+            break;
+        }
+    }
+
+    snapshot
+}
+
+/// Whether the walk would list `path`, checked by walking just its directory
+fn is_walked(path: &Path) -> bool {
+    let Some(parent) = path.parent() else {
+        return false;
+    };
+    walker(parent)
+        .max_depth(Some(1))
+        .build()
+        .flatten()
+        .any(|entry| entry.path() == path)
+}
+
+impl Index {
+    fn rebuild(&self) {
+        let snapshot = walk(&self.root);
+        if let Ok(mut current) = self.snapshot.write() {
+            *current = snapshot;
+        }
+        self.sync_watches();
+    }
+
+    /// Watch the directories of the current snapshot and stop watching the rest,
+    /// so ignored trees like `node_modules` never use up watches
+    fn sync_watches(&self) {
+        let Ok(mut watcher) = self.watcher.lock() else {
+            return;
+        };
+        let (Some(debouncer), Ok(mut watched), Ok(snapshot)) =
+            (watcher.as_mut(), self.watched.lock(), self.snapshot.read())
+        else {
+            return;
+        };
+
+        for dir in watched.difference(&snapshot.dirs) {
+            let _ = debouncer.watcher().unwatch(dir);
+        }
+        let mut failed = 0;
+        for dir in snapshot.dirs.difference(&watched) {
+            if debouncer
+                .watcher()
+                .watch(dir, RecursiveMode::NonRecursive)
+                .is_err()
+            {
+                failed += 1;
+            }
+        }
+        if failed > 0 {
+            log::warn!(
+                "couldn't watch {} directories under {}, their changes are missed",
+                failed,
+                self.root.display()
+            );
+        }
+        *watched = snapshot.dirs.clone();
+    }
+
+    /// Whether a changed path can affect the listing
+    ///
+    /// Edits to listed files don't, and neither do files the walk would skip.
+    fn affected_by(&self, path: &Path) -> bool {
+        if path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| IGNORE_FILES.contains(&n))
+        {
+            return true;
+        }
+
+        let Ok(snapshot) = self.snapshot.read() else {
+            return true;
+        };
+        let known = snapshot.listed.contains(path) || snapshot.dirs.contains(path);
+        let exists = path.symlink_metadata().is_ok();
+        match (known, exists) {
+            // Removed, or replaced by something of another type
+            (true, _) => !exists || path.is_dir() != snapshot.dirs.contains(path),
+            (false, true) => {
+                path.parent().is_some_and(|p| snapshot.dirs.contains(p)) && is_walked(path)
+            }
+            (false, false) => false,
+        }
+    }
+
+    /// Matching files, shortest paths first
+    fn search(&self, query: &str, limit: usize) -> Vec<ProjectFile> {
+        let query = query.to_lowercase();
+        // Dotfiles only show up when asked for
+        let show_hidden = query.starts_with('.');
+
+        let Ok(snapshot) = self.snapshot.read() else {
+            return Vec::new();
+        };
+        let mut matches: Vec<&IndexedFile> = snapshot
+            .files
+            .iter()
+            .filter(|f| (show_hidden || !f.hidden) && f.lowercase_path.contains(&query))
+            .collect();
+        matches.sort_by(|a, b| {
+            a.path
+                .len()
+                .cmp(&b.path.len())
+                .then_with(|| a.lowercase_path.cmp(&b.lowercase_path))
+        });
+
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|f| ProjectFile {
+                path: f.path.clone(),
+                name: f.name.clone(),
+                is_dir: false,
+            })
+            .collect()
+    }
+}
+
+/// Watch the index's directories and rebuild on changes that matter
+fn watch(index: &Arc<Index>) -> Result<(), String> {
+    let weak: Weak<Index> = Arc::downgrade(index);
+    let mut debouncer = new_debouncer(WATCH_DEBOUNCE, move |result: DebounceEventResult| {
+        let (Ok(events), Some(index)) = (result, weak.upgrade()) else {
+            return;
+        };
+        if events.iter().any(|event| index.affected_by(&event.path)) {
+            index.rebuild();
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    debouncer
+        .watcher()
+        .watch(&index.root, RecursiveMode::NonRecursive)
+        .map_err(|e| e.to_string())?;
+
+    if let Ok(mut watcher) = index.watcher.lock() {
+        *watcher = Some(debouncer);
+    }
+    index.sync_watches();
+    Ok(())
+}
+
+/// Walk and watch `root`; the index is still usable if watching fails
+fn build(root: &Path) -> Arc<Index> {
+    let index = Arc::new(Index {
+        root: root.to_path_buf(),
+        snapshot: RwLock::new(walk(root)),
+        watcher: Mutex::new(None),
+        watched: Mutex::new(HashSet::new()),
+    });
+    if let Err(e) = watch(&index) {
+        log::warn!("not caching {}: {}", root.display(), e);
+    }
+    index
+}
+
+/// The index for `root`, building and watching it on first use
+///
+/// Concurrent first queries for a root share one build. If the directory
+/// can't be watched the index is used once and not cached, so results are
+/// never stale.
+fn index_for(root: &Path) -> Arc<Index> {
+    let slot = {
+        let Ok(mut indexes) = INDEXES.lock() else {
+            return build(root);
+        };
+        if !indexes.contains_key(root) && indexes.len() >= MAX_INDEXES {
+            let oldest = indexes
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                indexes.remove(&oldest);
+            }
+        }
+        let cached = indexes.entry(root.to_path_buf()).or_insert_with(|| Cached {
+            index: Arc::new(OnceLock::new()),
+            last_used: Instant::now(),
+        });
+        cached.last_used = Instant::now();
+        cached.index.clone()
+    };
+
+    // Built outside the map's lock so one large project doesn't hold up the others
+    let index = slot.get_or_init(|| build(root)).clone();
+
+    let watched = index.watcher.lock().is_ok_and(|w| w.is_some());
+    if !watched {
+        if let Ok(mut indexes) = INDEXES.lock() {
+            if indexes
+                .get(root)
+                .is_some_and(|cached| Arc::ptr_eq(&cached.index, &slot))
+            {
+                indexes.remove(root);
+            }
+        }
+    }
+    index
+}
+
+/// Files under `directory` whose relative path contains `query` (case-insensitive)
+pub fn search(directory: &Path, query: &str, limit: usize) -> Result<Vec<ProjectFile>, String> {
+    let root = directory
+        .canonicalize()
+        .map_err(|_| "Directory does not exist".to_string())?;
+    if !root.is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+    Ok(index_for(&root).search(query, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> (tempfile::TempDir, PathBuf) {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        for (path, contents) in [
+            (".gitignore", "target/\n*.log\n"),
+            ("src/main.rs", ""),
+            ("src/debug.log", ""),
+            ("target/debug/out.rs", ""),
+            (".env", ""),
+            (".git/config", ""),
+        ] {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        (temp, root)
+    }
+
+    fn paths(root: &Path, query: &str) -> Vec<String> {
+        search(root, query, 100)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.path)
+            .collect()
+    }
+
+    /// Poll until `check` passes, the watcher reacts after its debounce
+    fn eventually(check: impl Fn() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if check() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        false
+    }
+
+    #[test]
+    fn honors_gitignore_and_hides_dotfiles() {
+        let (_temp, root) = project();
+        assert_eq!(paths(&root, ""), vec!["src/main.rs"]);
+        assert_eq!(paths(&root, ".e"), vec![".env"]);
+        assert!(paths(&root, ".git").iter().all(|p| p == ".gitignore"));
+    }
+
+    #[test]
+    fn watches_only_walked_directories() {
+        let (_temp, root) = project();
+        let index = index_for(&root);
+        let watched = index.watched.lock().unwrap().clone();
+        assert!(watched.contains(&root.join("src")));
+        assert!(!watched.contains(&root.join("target")));
+        assert!(!watched.contains(&root.join(".git")));
+    }
+
+    #[test]
+    fn concurrent_first_queries_share_one_index() {
+        let (_temp, root) = project();
+        let indexes: Vec<Arc<Index>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4).map(|_| scope.spawn(|| index_for(&root))).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert!(indexes.iter().all(|i| Arc::ptr_eq(i, &indexes[0])));
+    }
+
+    #[test]
+    fn picks_up_changes() {
+        let (_temp, root) = project();
+        assert_eq!(paths(&root, ""), vec!["src/main.rs"]);
+
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::write(root.join("src/nested/lib.rs"), "").unwrap();
+        std::fs::write(root.join("target/new.rs"), "").unwrap();
+        assert!(eventually(
+            || paths(&root, "") == ["src/main.rs", "src/nested/lib.rs"]
+        ));
+
+        std::fs::remove_file(root.join("src/main.rs")).unwrap();
+        assert!(eventually(|| paths(&root, "") == ["src/nested/lib.rs"]));
+
+        std::fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        assert!(eventually(|| paths(&root, "")
+            == [
+                "target/new.rs",
+                "src/nested/lib.rs",
+                "target/debug/out.rs"
+            ]));
+    }
+
+    #[test]
+    fn ignored_changes_do_not_count() {
+        let (_temp, root) = project();
+        let index = index_for(&root);
+        std::fs::write(root.join("src/other.log"), "").unwrap();
+        std::fs::write(root.join("src/new.rs"), "").unwrap();
+        assert!(!index.affected_by(&root.join("src/other.log")));
+        assert!(!index.affected_by(&root.join("src/main.rs")));
+        assert!(index.affected_by(&root.join("src/new.rs")));
+        assert!(index.affected_by(&root.join(".gitignore")));
+    }
+}
@@ -1,9 +1,6 @@
 #![allow(deprecated)]
 #![allow(dead_code)]
 
-use image::{DynamicImage, Rgba};
-use tauri::AppHandle;
-
 // macOS-specific imports and functions
 #[cfg(target_os = "macos")]
 mod macos {
@@ -234,67 +231,3 @@ pub use macos::{
     position_panel, position_permission_popup, setup_panel_listeners, swizzle_to_panel,
     update_panel_appearance,
 };
-
-/// Update the tray icon with a badge (e.g., "3" for 3 pending items)
-/// This function works on all platforms
-pub fn update_tray_icon_with_badge(
-    app_handle: &AppHandle,
-    badge: Option<&str>,
-) -> tauri::Result<()> {
-    use tauri::image::Image;
-
-    let base_icon_bytes = include_bytes!("../icons/tray-dark.png");
-    let base_icon = image::load_from_memory(base_icon_bytes).unwrap();
-
-    let final_icon = if let Some(text) = badge {
-        draw_badge_on_icon(&base_icon, text)?
-    } else {
-        base_icon
-    };
-
-    let mut buf = Vec::new();
-    final_icon
-        .write_to(&mut std::io::Cursor::new(&mut buf), image::ImageFormat::Png)
-        .map_err(|e| std::io::Error::other(e.to_string()))?;
-
-    let icon = Image::from_bytes(&buf)?;
-
-    if let Some(tray) = app_handle.tray_by_id("tray") {
-        tray.set_icon(Some(icon))?;
-    }
-
-    Ok(())
-}
-
-/// Draw a badge with text on the tray icon
-fn draw_badge_on_icon(base: &DynamicImage, _text: &str) -> tauri::Result<DynamicImage> {
-    let mut image = base.to_rgba8();
-    let (width, height) = image.dimensions();
-
-    // Badge size (smaller for tray icons)
-    let badge_size = width as i32 / 2;
-    let badge_x = width as i32 - badge_size;
-    let badge_y = 0;
-
-    // Draw red circle badge
-    let color = Rgba([255, 59, 48, 255]);
-    for y in 0..badge_size {
-        for x in 0..badge_size {
-            let dx = x - badge_size / 2;
-            let dy = y - badge_size / 2;
-            if dx * dx + dy * dy <= (badge_size / 2).pow(2) {
-                let px = (badge_x + x) as u32;
-                let py = (badge_y + y) as u32;
-                if px < width && py < height {
-                    image.put_pixel(px, py, color);
-                }
-            }
-        }
-    }
-
-    // For now, just draw the badge circle without text (simpler approach)
-    // Text rendering requires a font file, which complicates the build
-    // The badge color indicates there are pending items
-
-    Ok(DynamicImage::ImageRgba8(image))
-}
@@ -0,0 +1,228 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::notifications::NotificationEvent;
+
+const INBOX_FILE: &str = "notifications.json";
+
+// Oldest entries are dropped past this many
+const MAX_ENTRIES: usize = 200;
+
+// Loaded from disk on first use
+static INBOX: Mutex<Option<Inbox>> = Mutex::new(None);
+
+/// What `notifications.json` holds
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Inbox {
+    /// Never reused, even after the entries are cleared
+    next_id: u64,
+    /// Sent notifications, oldest first
+    entries: Vec<InboxEntry>,
+}
+
+impl Inbox {
+    fn empty() -> Self {
+        Self {
+            next_id: 1,
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// A notification we sent, kept after the toast is gone
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxEntry {
+    pub id: u64,
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+    pub kind: NotificationEvent,
+    pub title: String,
+    pub body: String,
+    pub instance_url: String,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub read: bool,
+}
+
+fn inbox_path(app_handle: &AppHandle) -> PathBuf {
+    app_handle
+        .path()
+        .app_data_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join(INBOX_FILE)
+}
+
+fn parse(contents: &[u8]) -> Result<Inbox, serde_json::Error> {
+    let mut inbox: Inbox = serde_json::from_slice(contents)?;
+    // Also covers a hand-edited counter
+    let after_last = inbox.entries.iter().map(|e| e.id + 1).max().unwrap_or(1);
+    inbox.next_id = inbox.next_id.max(after_last);
+    Ok(inbox)
+}
+
+fn load(app_handle: &AppHandle) -> Inbox {
+    let Ok(contents) = std::fs::read(inbox_path(app_handle)) else {
+        return Inbox::empty();
+    };
+    parse(&contents).unwrap_or_else(|e| {
+        log::warn!("ignoring unreadable {}: {}", INBOX_FILE, e);
+        Inbox::empty()
+    })
+}
+
+fn unread(inbox: &Inbox) -> usize {
+    inbox.entries.iter().filter(|e| !e.read).count()
+}
+
+/// Run `change` on a copy of the inbox; if it reports a change, save it, keep it
+/// and publish the unread count. Nothing changes if saving fails.
+fn update<T>(
+    app_handle: &AppHandle,
+    change: impl FnOnce(&mut Inbox) -> (T, bool),
+) -> Result<T, String> {
+    let mut guard = INBOX.lock().map_err(|e| e.to_string())?;
+    let inbox = guard.get_or_insert_with(|| load(app_handle));
+    let mut next = inbox.clone();
+    let (result, changed) = change(&mut next);
+    if !changed {
+        return Ok(result);
+    }
+
+    let json = serde_json::to_vec_pretty(&next).map_err(|e| e.to_string())?;
+    crate::storage::write_atomic(&inbox_path(app_handle), &json).map_err(|e| e.to_string())?;
+    let unread = unread(&next);
+    *inbox = next;
+    drop(guard);
+
+    let _ = app_handle.emit("notifications-changed", unread);
+    crate::tray_icon::set_unread(app_handle, unread);
+    Ok(result)
+}
+
+/// Load the inbox and show its unread count on the tray
+pub fn init(app_handle: &AppHandle) {
+    let unread = update(app_handle, |inbox| (unread(inbox), false));
+    if let Ok(unread) = unread {
+        crate::tray_icon::set_unread(app_handle, unread);
+    }
+}
+
+/// Keep a notification, dropping the oldest past `MAX_ENTRIES`
+pub fn record(
+    app_handle: &AppHandle,
+    kind: NotificationEvent,
+    title: &str,
+    body: &str,
+    instance_url: &str,
+    session_id: Option<&str>,
+) {
+    let result = update(app_handle, |inbox| {
+        let id = inbox.next_id;
+        inbox.next_id += 1;
+        inbox.entries.push(InboxEntry {
+            id,
+            timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            kind,
+            title: title.to_string(),
+            body: body.to_string(),
+            instance_url: instance_url.to_string(),
+            session_id: session_id.map(str::to_string),
+            read: false,
+        });
+        if inbox.entries.len() > MAX_ENTRIES {
+            let excess = inbox.entries.len() - MAX_ENTRIES;
+            inbox.entries.drain(..excess);
+        }
+        ((), true)
+    });
+    if let Err(e) = result {
+        log::error!("failed to save {}: {}", INBOX_FILE, e);
+    }
+}
+
+// --------------------------------------------
+// Commands
+// --------------------------------------------
+
+/// Sent notifications, newest first
+#[tauri::command]
+pub fn list_notifications(
+    app_handle: AppHandle,
+    unread_only: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<InboxEntry>, String> {
+    update(&app_handle, |inbox| {
+        let list = inbox
+            .entries
+            .iter()
+            .rev()
+            .filter(|e| !unread_only.unwrap_or(false) || !e.read)
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect();
+        (list, false)
+    })
+}
+
+/// Mark entries as read by ID, or all of them when `ids` is missing
+#[tauri::command]
+pub fn mark_read(app_handle: AppHandle, ids: Option<Vec<u64>>) -> Result<(), String> {
+    update(&app_handle, |inbox| {
+        let mut changed = false;
+        for entry in inbox.entries.iter_mut().filter(|e| !e.read) {
+            if ids.as_ref().is_none_or(|ids| ids.contains(&entry.id)) {
+                entry.read = true;
+                changed = true;
+            }
+        }
+        ((), changed)
+    })
+}
+
+#[tauri::command]
+pub fn clear_notifications(app_handle: AppHandle) -> Result<(), String> {
+    update(&app_handle, |inbox| {
+        let changed = !inbox.entries.is_empty();
+        inbox.entries.clear();
+        ((), changed)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u64) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "timestamp": 0,
+            "kind": "session_done",
+            "title": "Done",
+            "body": "",
+            "instanceUrl": "http://127.0.0.1:4096",
+        })
+    }
+
+    #[test]
+    fn keeps_the_counter_after_clearing() {
+        let cleared = br#"{ "nextId": 42, "entries": [] }"#;
+        assert_eq!(parse(cleared).unwrap().next_id, 42);
+        // A counter behind the entries is moved past them
+        let behind = serde_json::json!({ "nextId": 2, "entries": [entry(5)] });
+        assert_eq!(
+            parse(&serde_json::to_vec(&behind).unwrap())
+                .unwrap()
+                .next_id,
+            6
+        );
+        assert_eq!(
+            parse(br#"{ "nextId": 0, "entries": [] }"#).unwrap().next_id,
+            1
+        );
+    }
+}
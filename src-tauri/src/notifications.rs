@@ -0,0 +1,791 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{Local, NaiveTime};
+use opentray_client::{Client, TodoItem, TodoStatus};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::permissions::PermissionData;
+use crate::sound::SoundCategory;
+use crate::tray_menu::OpenSession;
+
+// Notifications arriving within this window are shown together
+const COALESCE_WINDOW: Duration = Duration::from_millis(1500);
+
+// Lines listed in a coalesced notification before "and N more"
+const SUMMARY_LINES: usize = 3;
+
+// Queued notifications waiting for the window to close
+static PENDING: Mutex<Vec<Notice>> = Mutex::new(Vec::new());
+static FLUSH_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+// Sessions whose todo list was last seen fully done, by (instance URL, session ID);
+// forgotten when the session is deleted or its instance goes away
+static TODOS_DONE: Mutex<Option<HashSet<(String, String)>>> = Mutex::new(None);
+
+/// Something worth a notification
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    /// A session went idle after working
+    SessionDone,
+    SessionError,
+    PermissionRequired,
+    /// Every item of a session's todo list is done
+    TodosCompleted,
+}
+
+impl NotificationEvent {
+    fn sound(self) -> SoundCategory {
+        match self {
+            NotificationEvent::SessionDone | NotificationEvent::TodosCompleted => {
+                SoundCategory::Done
+            }
+            NotificationEvent::SessionError => SoundCategory::Error,
+            NotificationEvent::PermissionRequired => SoundCategory::Permission,
+        }
+    }
+}
+
+/// Turns notifications on or off, stored in `NotificationSettings.rules`
+///
+/// Every field that is set must match; `directory` is a glob against the
+/// instance's project directory.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct NotificationRule {
+    #[serde(default)]
+    pub event: Option<NotificationEvent>,
+    #[serde(default)]
+    pub directory: Option<String>,
+    pub enabled: bool,
+}
+
+/// Local time range without notifications, e.g. 22:00 to 07:00
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct NotificationSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// First matching rule wins, events without one are notified
+    #[serde(default)]
+    pub rules: Vec<NotificationRule>,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            rules: Vec::new(),
+            quiet_hours: None,
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// One notification before coalescing
+#[derive(Clone, Debug)]
+struct Notice {
+    event: NotificationEvent,
+    title: String,
+    body: String,
+    instance_url: String,
+    session_id: Option<String>,
+    /// What clicking it opens, unset for summaries
+    target: Option<Target>,
+}
+
+impl Notice {
+    /// Both are about the same permission request
+    fn same_request(&self, other: &Notice) -> bool {
+        matches!(
+            (&self.target, &other.target),
+            (Some(Target::Permission(a)), Some(Target::Permission(b))) if a == b
+        )
+    }
+}
+
+// Only read where notifications report clicks
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+#[derive(Clone, Debug)]
+enum Target {
+    /// A queued permission request by ID, answerable from the notification
+    Permission(String),
+    Session(OpenSession),
+}
+
+// --------------------------------------------
+// Rules
+// --------------------------------------------
+
+/// Whether `event` for an instance in `directory` should notify, ignoring quiet hours
+pub fn wants(settings: &NotificationSettings, event: NotificationEvent, directory: &str) -> bool {
+    if !settings.enabled {
+        return false;
+    }
+    settings
+        .rules
+        .iter()
+        .find(|rule| {
+            rule.event.is_none_or(|e| e == event)
+                && rule
+                    .directory
+                    .as_deref()
+                    .is_none_or(|glob| crate::policy::path_matches(glob, directory))
+        })
+        .is_none_or(|rule| rule.enabled)
+}
+
+/// Whether `now` falls inside the quiet hours, which may wrap past midnight
+pub fn is_quiet(quiet_hours: Option<&QuietHours>, now: NaiveTime) -> bool {
+    let Some(quiet) = quiet_hours else {
+        return false;
+    };
+    let (Ok(start), Ok(end)) = (
+        NaiveTime::parse_from_str(&quiet.start, "%H:%M"),
+        NaiveTime::parse_from_str(&quiet.end, "%H:%M"),
+    ) else {
+        return false;
+    };
+
+    if start <= end {
+        start <= now && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+fn directory_of(instance_url: &str) -> String {
+    crate::discovery::instances()
+        .into_iter()
+        .find(|i| i.url == instance_url)
+        .map(|i| i.directory)
+        .unwrap_or_default()
+}
+
+/// Check the rules for an event from `instance_url`
+fn should_notify(app_handle: &AppHandle, event: NotificationEvent, instance_url: &str) -> bool {
+    let settings = crate::settings::get(app_handle).notifications;
+    wants(&settings, event, &directory_of(instance_url))
+}
+
+// --------------------------------------------
+// Events
+// --------------------------------------------
+
+/// A session finished working
+pub fn session_done(app_handle: &AppHandle, instance_url: &str, session_id: &str) {
+    let event = NotificationEvent::SessionDone;
+    if !should_notify(app_handle, event, instance_url) {
+        return;
+    }
+    notify_for_session(
+        app_handle,
+        event,
+        "Session finished",
+        instance_url,
+        session_id,
+        |title| title,
+    );
+}
+
+pub fn session_error(
+    app_handle: &AppHandle,
+    instance_url: &str,
+    session_id: &str,
+    error: &serde_json::Value,
+) {
+    let event = NotificationEvent::SessionError;
+    if !should_notify(app_handle, event, instance_url) {
+        return;
+    }
+    let message = error["data"]["message"]
+        .as_str()
+        .or_else(|| error["name"].as_str())
+        .map(|m| m.to_string());
+    notify_for_session(
+        app_handle,
+        event,
+        "Session failed",
+        instance_url,
+        session_id,
+        move |title| match message {
+            Some(message) => format!("{}: {}", title, message),
+            None => title,
+        },
+    );
+}
+
+/// A request is waiting in the queue for an answer
+pub fn permission_required(app_handle: &AppHandle, data: &PermissionData) {
+    let event = NotificationEvent::PermissionRequired;
+    if !should_notify(app_handle, event, &data.instance_url) {
+        return;
+    }
+    let permission = data.request["permission"].as_str().unwrap_or("permission");
+    let body = match data.request["patterns"][0].as_str() {
+        Some(pattern) => format!("{} wants {}: {}", data.session_title, permission, pattern),
+        None => format!("{} wants {}", data.session_title, permission),
+    };
+    queue(
+        app_handle,
+        Notice {
+            event,
+            title: "Permission required".to_string(),
+            body,
+            instance_url: data.instance_url.clone(),
+            session_id: data.request["sessionID"].as_str().map(str::to_string),
+            target: Some(Target::Permission(data.id.clone())),
+        },
+    );
+}
+
+/// Record whether a session's todos are all done, returns whether they just became so
+fn track_done(seen: &mut HashSet<(String, String)>, key: (String, String), done: bool) -> bool {
+    if done {
+        seen.insert(key)
+    } else {
+        seen.remove(&key);
+        false
+    }
+}
+
+/// Forget the todo state of a deleted session
+pub fn forget_session(instance_url: &str, session_id: &str) {
+    if let Ok(mut guard) = TODOS_DONE.lock() {
+        if let Some(seen) = guard.as_mut() {
+            seen.retain(|(url, id)| url != instance_url || id != session_id);
+        }
+    }
+}
+
+/// Forget the todo state of every session of an instance that went away
+pub fn forget_instance(instance_url: &str) {
+    if let Ok(mut guard) = TODOS_DONE.lock() {
+        if let Some(seen) = guard.as_mut() {
+            seen.retain(|(url, _)| url != instance_url);
+        }
+    }
+}
+
+/// Notify once when every todo of a session is done
+pub fn todos_updated(
+    app_handle: &AppHandle,
+    instance_url: &str,
+    session_id: &str,
+    todos: &[serde_json::Value],
+) {
+    let todos: Vec<TodoItem> = todos
+        .iter()
+        .filter_map(|t| serde_json::from_value(t.clone()).ok())
+        .collect();
+    let done = todos.iter().any(|t| t.status == TodoStatus::Completed)
+        && todos
+            .iter()
+            .all(|t| matches!(t.status, TodoStatus::Completed | TodoStatus::Cancelled));
+
+    let key = (instance_url.to_string(), session_id.to_string());
+    let newly_done = TODOS_DONE
+        .lock()
+        .map(|mut guard| track_done(guard.get_or_insert_with(HashSet::new), key, done))
+        .unwrap_or(false);
+
+    let event = NotificationEvent::TodosCompleted;
+    if !newly_done || !should_notify(app_handle, event, instance_url) {
+        return;
+    }
+    let count = todos.len();
+    notify_for_session(
+        app_handle,
+        event,
+        "Todo list completed",
+        instance_url,
+        session_id,
+        move |title| {
+            format!(
+                "{}: {} item{} done",
+                title,
+                count,
+                if count == 1 { "" } else { "s" }
+            )
+        },
+    );
+}
+
+/// Look up the session, then queue a notice with a body built from its title,
+/// opening its chat
+fn notify_for_session(
+    app_handle: &AppHandle,
+    event: NotificationEvent,
+    title: &str,
+    instance_url: &str,
+    session_id: &str,
+    body: impl FnOnce(String) -> String + Send + 'static,
+) {
+    let app_handle = app_handle.clone();
+    let mut notice = Notice {
+        event,
+        title: title.to_string(),
+        body: String::new(),
+        instance_url: instance_url.to_string(),
+        session_id: Some(session_id.to_string()),
+        target: None,
+    };
+    let session_id = session_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let directory = directory_of(&notice.instance_url);
+        let client = Client::new(&notice.instance_url).directory(&directory);
+        match client.session(&session_id).await {
+            Ok(session) => {
+                notice.body = body(session.title.clone());
+                notice.target = Some(Target::Session(OpenSession {
+                    instance_url: notice.instance_url.clone(),
+                    directory,
+                    session,
+                }));
+            }
+            Err(_) => notice.body = body("Unknown session".to_string()),
+        }
+        queue(&app_handle, notice);
+    });
+}
+
+// --------------------------------------------
+// Delivery
+// --------------------------------------------
+
+/// Keep a notice in the inbox, then hold it until the coalescing window closes
+///
+/// Quiet hours only silence the toast; the inbox still gets the notice.
+fn queue(app_handle: &AppHandle, notice: Notice) {
+    crate::inbox::record(
+        app_handle,
+        notice.event,
+        &notice.title,
+        &notice.body,
+        &notice.instance_url,
+        notice.session_id.as_deref(),
+    );
+
+    let quiet_hours = crate::settings::get(app_handle).notifications.quiet_hours;
+    if is_quiet(quiet_hours.as_ref(), Local::now().time()) {
+        return;
+    }
+
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.push(notice);
+    }
+    if FLUSH_SCHEDULED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(COALESCE_WINDOW).await;
+        FLUSH_SCHEDULED.store(false, Ordering::SeqCst);
+        let notices = PENDING
+            .lock()
+            .map(|mut p| std::mem::take(&mut *p))
+            .unwrap_or_default();
+        for notice in coalesce(notices) {
+            show(&app_handle, &notice);
+        }
+    });
+}
+
+/// Merge notices of the same event into one summary, keeping first-seen order
+///
+/// A notice left on its own keeps its target, so a single permission request
+/// still gets its Allow and Deny buttons.
+fn coalesce(notices: Vec<Notice>) -> Vec<Notice> {
+    let mut groups: Vec<Vec<Notice>> = Vec::new();
+    for notice in notices {
+        match groups.iter_mut().find(|g| g[0].event == notice.event) {
+            // The same request queued twice is one request
+            Some(group) if group.iter().any(|n| n.same_request(&notice)) => {}
+            Some(group) => group.push(notice),
+            None => groups.push(vec![notice]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|mut group| {
+            if group.len() == 1 {
+                return group.remove(0);
+            }
+            let count = group.len();
+            let title = match group[0].event {
+                NotificationEvent::SessionDone => format!("{} sessions finished", count),
+                NotificationEvent::SessionError => format!("{} sessions failed", count),
+                NotificationEvent::PermissionRequired => {
+                    format!("{} permission requests", count)
+                }
+                NotificationEvent::TodosCompleted => format!("{} todo lists completed", count),
+            };
+            let mut lines: Vec<String> = group
+                .iter()
+                .take(SUMMARY_LINES)
+                .map(|n| n.body.clone())
+                .collect();
+            if count > SUMMARY_LINES {
+                lines.push(format!("and {} more", count - SUMMARY_LINES));
+            }
+            Notice {
+                title,
+                body: lines.join("\n"),
+                target: None,
+                ..group.remove(0)
+            }
+        })
+        .collect()
+}
+
+/// Show a notice, with our own sound rather than the platform's so each event
+/// can have its own
+fn show(app_handle: &AppHandle, notice: &Notice) {
+    crate::sound::play(app_handle, notice.event.sound());
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if actions::show(app_handle, notice) {
+        return;
+    }
+
+    let result = app_handle
+        .notification()
+        .builder()
+        .title(&notice.title)
+        .body(&notice.body)
+        .show();
+    if let Err(e) = result {
+        log::warn!("failed to show {:?}: {}", notice.event, e);
+    }
+}
+
+// --------------------------------------------
+// Actions
+// --------------------------------------------
+
+/// Notifications with buttons and click handling, for freedesktop notification servers
+///
+/// The plugin can't report clicks, so these go to the server through notify-rust.
+/// Servers without the `actions` capability get the plugin's plain notification.
+#[cfg(all(unix, not(target_os = "macos")))]
+mod actions {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::LazyLock;
+
+    use notify_rust::{Notification, Timeout};
+    use opentray_client::PermissionReply;
+    use tauri::AppHandle;
+
+    use super::{Notice, NotificationEvent, Target};
+    use crate::command::reply_queued;
+
+    // The server's default action, sent when the body is clicked
+    const DEFAULT: &str = "default";
+    const ALLOW: &str = "allow";
+    const DENY: &str = "deny";
+
+    // Each notification with buttons needs a thread blocked until it's clicked or
+    // closed; past this many, notifications are shown without buttons
+    const MAX_WAITING: usize = 8;
+
+    // Servers that honor it close the notification then, which ends its thread
+    const EXPIRE_AFTER: Timeout = Timeout::Milliseconds(5 * 60 * 1000);
+
+    static WAITING: AtomicUsize = AtomicUsize::new(0);
+
+    /// One of the `MAX_WAITING` threads, given back when dropped
+    struct Slot;
+
+    impl Slot {
+        fn take() -> Option<Self> {
+            WAITING
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                    (n < MAX_WAITING).then_some(n + 1)
+                })
+                .ok()
+                .map(|_| Slot)
+        }
+    }
+
+    impl Drop for Slot {
+        fn drop(&mut self) {
+            WAITING.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    static SUPPORTED: LazyLock<bool> = LazyLock::new(|| {
+        notify_rust::get_capabilities()
+            .map(|caps| caps.iter().any(|c| c == "actions"))
+            .unwrap_or(false)
+    });
+
+    /// Show `notice` and wait for a click on a background thread; false if unsupported
+    /// or too many notifications are already waiting
+    pub fn show(app_handle: &AppHandle, notice: &Notice) -> bool {
+        if !*SUPPORTED {
+            return false;
+        }
+        let Some(slot) = Slot::take() else {
+            return false;
+        };
+
+        let mut notification = Notification::new();
+        notification
+            .appname(&app_handle.package_info().name)
+            .summary(&notice.title)
+            .body(&notice.body)
+            .auto_icon()
+            .timeout(EXPIRE_AFTER)
+            .action(DEFAULT, "Open");
+        if let Some(Target::Permission(_)) = notice.target {
+            notification.action(ALLOW, "Allow").action(DENY, "Deny");
+        }
+
+        let handle = match notification.show() {
+            Ok(handle) => handle,
+            Err(e) => {
+                log::info!("falling back to plain notification: {}", e);
+                return false;
+            }
+        };
+
+        let app_handle = app_handle.clone();
+        let event = notice.event;
+        let target = notice.target.clone();
+        std::thread::spawn(move || {
+            let _slot = slot;
+            handle.wait_for_action(|action| match (action, target) {
+                (ALLOW, Some(Target::Permission(id))) => {
+                    reply_queued(&app_handle, &id, PermissionReply::Once)
+                }
+                (DENY, Some(Target::Permission(id))) => {
+                    reply_queued(&app_handle, &id, PermissionReply::Reject)
+                }
+                (DEFAULT, target) => open(&app_handle, event, target.as_ref()),
+                _ => {}
+            });
+        });
+        true
+    }
+
+    /// What clicking the body does: the chat, the permission popup, or just the panel
+    fn open(app_handle: &AppHandle, event: NotificationEvent, target: Option<&Target>) {
+        match target {
+            Some(Target::Session(open)) => crate::tray_menu::open_session(app_handle, open),
+            _ if event == NotificationEvent::PermissionRequired => {
+                crate::command::present_permission_popup(app_handle)
+            }
+            _ => crate::command::show_panel_internal(app_handle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(text, "%H:%M").unwrap()
+    }
+
+    fn quiet(start: &str, end: &str) -> QuietHours {
+        QuietHours {
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    fn rule(
+        event: Option<NotificationEvent>,
+        directory: Option<&str>,
+        enabled: bool,
+    ) -> NotificationRule {
+        NotificationRule {
+            event,
+            directory: directory.map(str::to_string),
+            enabled,
+        }
+    }
+
+    fn notice(event: NotificationEvent, body: &str, target: Option<Target>) -> Notice {
+        Notice {
+            event,
+            title: "Title".to_string(),
+            body: body.to_string(),
+            instance_url: "http://127.0.0.1:4096".to_string(),
+            session_id: None,
+            target,
+        }
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day() {
+        let hours = quiet("12:00", "14:00");
+        assert!(!is_quiet(Some(&hours), time("11:59")));
+        assert!(is_quiet(Some(&hours), time("12:00")));
+        assert!(is_quiet(Some(&hours), time("13:59")));
+        assert!(!is_quiet(Some(&hours), time("14:00")));
+    }
+
+    #[test]
+    fn quiet_hours_wrap_past_midnight() {
+        let hours = quiet("22:00", "07:00");
+        assert!(is_quiet(Some(&hours), time("23:30")));
+        assert!(is_quiet(Some(&hours), time("00:00")));
+        assert!(is_quiet(Some(&hours), time("06:59")));
+        assert!(!is_quiet(Some(&hours), time("07:00")));
+        assert!(!is_quiet(Some(&hours), time("21:59")));
+    }
+
+    #[test]
+    fn no_or_invalid_quiet_hours_never_silence() {
+        assert!(!is_quiet(None, time("03:00")));
+        assert!(!is_quiet(Some(&quiet("late", "07:00")), time("03:00")));
+        // An empty range is never quiet
+        assert!(!is_quiet(Some(&quiet("09:00", "09:00")), time("09:00")));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let settings = NotificationSettings {
+            rules: vec![
+                rule(None, Some("/work/noisy"), false),
+                rule(Some(NotificationEvent::SessionDone), None, false),
+                rule(None, Some("/work/**"), true),
+            ],
+            ..Default::default()
+        };
+        let done = NotificationEvent::SessionDone;
+        let error = NotificationEvent::SessionError;
+        assert!(!wants(&settings, error, "/work/noisy"));
+        assert!(!wants(&settings, done, "/work/app"));
+        assert!(wants(&settings, error, "/work/app"));
+        // No rule matches, so it notifies
+        assert!(wants(&settings, error, "/home/me"));
+    }
+
+    #[test]
+    fn directory_rules_use_path_globs() {
+        let settings = NotificationSettings {
+            rules: vec![rule(None, Some("/work/*"), false)],
+            ..Default::default()
+        };
+        let event = NotificationEvent::SessionDone;
+        assert!(!wants(&settings, event, "/work/app"));
+        assert!(wants(&settings, event, "/work/app/nested"));
+    }
+
+    #[test]
+    fn disabled_settings_never_notify() {
+        let settings = NotificationSettings {
+            enabled: false,
+            rules: vec![rule(None, None, true)],
+            ..Default::default()
+        };
+        assert!(!wants(
+            &settings,
+            NotificationEvent::PermissionRequired,
+            "/work"
+        ));
+    }
+
+    #[test]
+    fn coalesces_by_event_in_first_seen_order() {
+        let done = NotificationEvent::SessionDone;
+        let error = NotificationEvent::SessionError;
+        let notices = vec![
+            notice(done, "one", None),
+            notice(error, "broke", None),
+            notice(done, "two", None),
+        ];
+        let shown = coalesce(notices);
+        assert_eq!(shown.len(), 2);
+        assert_eq!(shown[0].title, "2 sessions finished");
+        assert_eq!(shown[0].body, "one\ntwo");
+        assert_eq!(shown[1].title, "Title");
+        assert_eq!(shown[1].body, "broke");
+    }
+
+    #[test]
+    fn summarizes_past_the_line_limit() {
+        let notices = (1..=5)
+            .map(|i| notice(NotificationEvent::TodosCompleted, &i.to_string(), None))
+            .collect();
+        let shown = coalesce(notices);
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].title, "5 todo lists completed");
+        assert_eq!(shown[0].body, "1\n2\n3\nand 2 more");
+    }
+
+    #[test]
+    fn single_permission_keeps_its_actions() {
+        let event = NotificationEvent::PermissionRequired;
+        let request = |id: &str| Some(Target::Permission(id.to_string()));
+
+        let shown = coalesce(vec![
+            notice(event, "bash", request("per_1")),
+            notice(event, "bash", request("per_1")),
+            notice(NotificationEvent::SessionDone, "done", None),
+        ]);
+        assert_eq!(shown.len(), 2);
+        assert!(matches!(&shown[0].target, Some(Target::Permission(id)) if id == "per_1"));
+
+        let shown = coalesce(vec![
+            notice(event, "bash", request("per_1")),
+            notice(event, "edit", request("per_2")),
+        ]);
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].title, "2 permission requests");
+        assert!(shown[0].target.is_none());
+    }
+
+    #[test]
+    fn todos_notify_once_until_forgotten() {
+        let key = |url: &str, id: &str| (url.to_string(), id.to_string());
+        let mut seen = HashSet::new();
+        assert!(track_done(&mut seen, key("a", "s1"), true));
+        assert!(!track_done(&mut seen, key("a", "s1"), true));
+        assert!(!track_done(&mut seen, key("a", "s1"), false));
+        assert!(track_done(&mut seen, key("a", "s1"), true));
+
+        // The global set is shared, so this test uses its own instance URLs
+        let url = "http://forget.test";
+        let other = "http://forget-other.test";
+        TODOS_DONE
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashSet::new)
+            .extend([key(url, "s1"), key(url, "s2"), key(other, "s1")]);
+        let remaining = || {
+            let mut keys: Vec<_> = TODOS_DONE
+                .lock()
+                .unwrap()
+                .iter()
+                .flatten()
+                .filter(|(u, _)| u == url || u == other)
+                .cloned()
+                .collect();
+            keys.sort();
+            keys
+        };
+        forget_session(url, "s1");
+        assert_eq!(remaining(), vec![key(other, "s1"), key(url, "s2")]);
+        forget_instance(url);
+        assert_eq!(remaining(), vec![key(other, "s1")]);
+    }
+}
@@ -0,0 +1,935 @@
+use std::sync::LazyLock;
+
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::sandbox::FileAccessError;
+
+// Subset of https://opencode.ai/config.json covering the keys OpenTray edits
+static SCHEMA: &str = include_str!("../schemas/opencode.schema.json");
+
+// Objects and arrays nested deeper than this are refused rather than overflowing the stack
+const MAX_DEPTH: usize = 256;
+
+// Some editors start the file with a byte order mark; OpenCode accepts it and so do we
+const BOM: &str = "\u{feff}";
+
+static VALIDATOR: LazyLock<jsonschema::Validator> = LazyLock::new(|| {
+    let schema: Value = serde_json::from_str(SCHEMA).expect("bundled schema is valid JSON");
+    jsonschema::validator_for(&schema).expect("bundled schema compiles")
+});
+
+/// Error returned by the config commands, serialized as `{ kind, message }`
+#[derive(serde::Serialize, Debug)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum ConfigError {
+    /// Not valid JSONC, the message includes line and column
+    Syntax(String),
+    /// Valid JSONC that doesn't match the config schema
+    Schema(String),
+    /// A key path runs through a value that isn't an object
+    InvalidEdit(String),
+    File(String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Syntax(msg) => write!(f, "Invalid JSON: {}", msg),
+            ConfigError::Schema(msg) => write!(f, "Invalid config: {}", msg),
+            ConfigError::InvalidEdit(msg) | ConfigError::File(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<FileAccessError> for ConfigError {
+    fn from(e: FileAccessError) -> Self {
+        ConfigError::File(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::File(e.to_string())
+    }
+}
+
+/// A targeted edit: set the value at `path`, or remove the key when `value` is null
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct ConfigEdit {
+    pub path: Vec<String>,
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+// --------------------------------------------
+// Parsing
+// --------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Tok {
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Colon,
+    Comma,
+    Str,
+    Literal,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token {
+    kind: Tok,
+    start: usize,
+    end: usize,
+}
+
+/// Syntax tree with byte offsets into the source, so edits can splice the
+/// original text and leave comments and formatting alone
+#[derive(Debug)]
+enum Node {
+    Object {
+        start: usize,
+        end: usize,
+        members: Vec<Member>,
+    },
+    Array {
+        end: usize,
+        items: Vec<Node>,
+    },
+    Scalar {
+        end: usize,
+        value: Value,
+    },
+}
+
+#[derive(Debug)]
+struct Member {
+    key: String,
+    /// Offset of the key's opening quote
+    start: usize,
+    value_start: usize,
+    value: Node,
+    /// Offset of the comma following the value, if any
+    comma: Option<usize>,
+}
+
+impl Node {
+    fn end(&self) -> usize {
+        match self {
+            Node::Object { end, .. } | Node::Array { end, .. } | Node::Scalar { end, .. } => *end,
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        match self {
+            Node::Object { members, .. } => Value::Object(
+                members
+                    .iter()
+                    .map(|m| (m.key.clone(), m.value.to_value()))
+                    .collect(),
+            ),
+            Node::Array { items, .. } => Value::Array(items.iter().map(Node::to_value).collect()),
+            Node::Scalar { value, .. } => value.clone(),
+        }
+    }
+}
+
+/// `line:column` (1-based) of a byte offset
+fn location(text: &str, offset: usize) -> String {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    format!("{}:{}", line, column)
+}
+
+fn syntax_error(text: &str, offset: usize, what: &str) -> ConfigError {
+    ConfigError::Syntax(format!("{} at {}", what, location(text, offset)))
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, ConfigError> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    // Skipped like whitespace, so edits splice around it and keep it
+    let mut i = if text.starts_with(BOM) { BOM.len() } else { 0 };
+
+    while i < bytes.len() {
+        let start = i;
+        let kind = match bytes[i] {
+            b' ' | b'\t' | b'\r' | b'\n' => {
+                i += 1;
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let close = text[i + 2..]
+                    .find("*/")
+                    .ok_or_else(|| syntax_error(text, i, "Unterminated comment"))?;
+                i += close + 4;
+                continue;
+            }
+            b'{' => Tok::LBrace,
+            b'}' => Tok::RBrace,
+            b'[' => Tok::LBracket,
+            b']' => Tok::RBracket,
+            b':' => Tok::Colon,
+            b',' => Tok::Comma,
+            b'"' => {
+                i += 1;
+                loop {
+                    match bytes.get(i) {
+                        None | Some(b'\n') => {
+                            return Err(syntax_error(text, start, "Unterminated string"))
+                        }
+                        Some(b'\\') => i += 2,
+                        Some(b'"') => break,
+                        Some(_) => i += 1,
+                    }
+                }
+                Tok::Str
+            }
+            _ => {
+                // Numbers, true/false/null; anything else fails when decoded
+                while i < bytes.len() && !b" \t\r\n{}[]:,\"/".contains(&bytes[i]) {
+                    i += 1;
+                }
+                // A `/` that doesn't start a comment
+                if i == start {
+                    return Err(syntax_error(text, start, "Expected a value"));
+                }
+                tokens.push(Token {
+                    kind: Tok::Literal,
+                    start,
+                    end: i,
+                });
+                continue;
+            }
+        };
+        i += 1;
+        tokens.push(Token {
+            kind,
+            start,
+            end: i,
+        });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Objects and arrays currently open
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self, what: &str) -> Result<Token, ConfigError> {
+        let token = self.peek().ok_or_else(|| {
+            syntax_error(self.text, self.text.len(), &format!("Expected {}", what))
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, kind: Tok, what: &str) -> Result<Token, ConfigError> {
+        let token = self.next(what)?;
+        if token.kind != kind {
+            return Err(syntax_error(
+                self.text,
+                token.start,
+                &format!("Expected {}", what),
+            ));
+        }
+        Ok(token)
+    }
+
+    /// Consume a comma if one follows, returning its offset
+    fn comma(&mut self) -> Option<usize> {
+        let token = self.peek().filter(|t| t.kind == Tok::Comma)?;
+        self.pos += 1;
+        Some(token.start)
+    }
+
+    fn value(&mut self) -> Result<Node, ConfigError> {
+        let token = self.next("a value")?;
+        match token.kind {
+            Tok::LBrace | Tok::LBracket => {
+                if self.depth >= MAX_DEPTH {
+                    return Err(syntax_error(self.text, token.start, "Nested too deeply"));
+                }
+                self.depth += 1;
+                let node = if token.kind == Tok::LBrace {
+                    self.object(token.start)
+                } else {
+                    self.array()
+                };
+                self.depth -= 1;
+                node
+            }
+            Tok::Str | Tok::Literal => {
+                let raw = &self.text[token.start..token.end];
+                let value = serde_json::from_str(raw).map_err(|_| {
+                    syntax_error(self.text, token.start, &format!("Invalid value `{}`", raw))
+                })?;
+                Ok(Node::Scalar {
+                    end: token.end,
+                    value,
+                })
+            }
+            _ => Err(syntax_error(self.text, token.start, "Expected a value")),
+        }
+    }
+
+    fn object(&mut self, start: usize) -> Result<Node, ConfigError> {
+        let mut members = Vec::new();
+        loop {
+            if let Some(close) = self.peek().filter(|t| t.kind == Tok::RBrace) {
+                self.pos += 1;
+                return Ok(Node::Object {
+                    start,
+                    end: close.end,
+                    members,
+                });
+            }
+
+            let key_token = self.expect(Tok::Str, "a key or `}`")?;
+            let key = serde_json::from_str(&self.text[key_token.start..key_token.end])
+                .map_err(|_| syntax_error(self.text, key_token.start, "Invalid key"))?;
+            let colon = self.expect(Tok::Colon, "`:`")?;
+            let value_start = self.peek().map(|t| t.start).unwrap_or(colon.end);
+            let value = self.value()?;
+            let comma = self.comma();
+            let done = comma.is_none();
+            members.push(Member {
+                key,
+                start: key_token.start,
+                value_start,
+                value,
+                comma,
+            });
+
+            if done {
+                let close = self.expect(Tok::RBrace, "`,` or `}`")?;
+                return Ok(Node::Object {
+                    start,
+                    end: close.end,
+                    members,
+                });
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Node, ConfigError> {
+        let mut items = Vec::new();
+        loop {
+            if let Some(close) = self.peek().filter(|t| t.kind == Tok::RBracket) {
+                self.pos += 1;
+                return Ok(Node::Array {
+                    end: close.end,
+                    items,
+                });
+            }
+
+            items.push(self.value()?);
+            if self.comma().is_none() {
+                let close = self.expect(Tok::RBracket, "`,` or `]`")?;
+                return Ok(Node::Array {
+                    end: close.end,
+                    items,
+                });
+            }
+        }
+    }
+}
+
+fn parse_tree(text: &str) -> Result<Node, ConfigError> {
+    let mut parser = Parser {
+        text,
+        tokens: tokenize(text)?,
+        pos: 0,
+        depth: 0,
+    };
+    let root = parser.value()?;
+    if let Some(extra) = parser.peek() {
+        return Err(syntax_error(
+            text,
+            extra.start,
+            "Unexpected content after the config",
+        ));
+    }
+    Ok(root)
+}
+
+/// Whether the file holds no config at all, not even `{}`
+fn is_blank(text: &str) -> bool {
+    text.strip_prefix(BOM).unwrap_or(text).trim().is_empty()
+}
+
+/// Parse JSON with comments and trailing commas, as OpenCode accepts
+pub fn parse(text: &str) -> Result<Value, ConfigError> {
+    Ok(parse_tree(text)?.to_value())
+}
+
+/// Check a parsed config against the bundled schema
+pub fn validate(config: &Value) -> Result<(), ConfigError> {
+    let errors: Vec<String> = VALIDATOR
+        .iter_errors(config)
+        .map(|e| {
+            let path = e.instance_path().to_string();
+            if path.is_empty() {
+                e.to_string()
+            } else {
+                format!("{}: {}", path, e)
+            }
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigError::Schema(errors.join("; ")))
+    }
+}
+
+// --------------------------------------------
+// Editing
+// --------------------------------------------
+
+fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0)
+}
+
+/// Leading whitespace of the line containing `offset`
+fn line_indent(text: &str, offset: usize) -> &str {
+    let start = line_start(text, offset);
+    let line = &text[start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// One level of indentation, taken from the first indented line
+fn indent_unit(text: &str) -> String {
+    text.lines()
+        .map(|line| &line[..line.len() - line.trim_start_matches([' ', '\t']).len()])
+        .find(|indent| !indent.is_empty())
+        .map(|indent| {
+            if indent.starts_with('\t') {
+                "\t".to_string()
+            } else {
+                indent.to_string()
+            }
+        })
+        .unwrap_or_else(|| "  ".to_string())
+}
+
+/// Pretty-print a value whose first line starts at `base` indentation
+fn render(value: &Value, unit: &str, base: &str) -> String {
+    use serde::Serialize;
+
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(unit.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    value
+        .serialize(&mut serializer)
+        .expect("serializing a Value can't fail");
+
+    String::from_utf8(out)
+        .unwrap_or_default()
+        .replace('\n', &format!("\n{}", base))
+}
+
+fn render_key(key: &str) -> String {
+    serde_json::to_string(key).unwrap_or_default()
+}
+
+/// Past trailing spaces and comments on the same line, before the newline
+///
+/// A `/* */` comment counts only if it closes on that line.
+fn end_of_line_content(text: &str, offset: usize) -> usize {
+    let mut at = offset;
+    loop {
+        let rest = &text[at..];
+        let trimmed = rest.trim_start_matches([' ', '\t']);
+        if trimmed.starts_with("//") {
+            return at + rest.find('\n').unwrap_or(rest.len());
+        }
+        if let Some(comment) = trimmed.strip_prefix("/*") {
+            let line = &comment[..comment.find('\n').unwrap_or(comment.len())];
+            if let Some(close) = line.find("*/") {
+                at += rest.len() - comment.len() + close + 2;
+                continue;
+            }
+        }
+        return at;
+    }
+}
+
+/// Apply replacements of `[start, end)` ranges, which must not overlap
+fn splice(text: &str, mut edits: Vec<(usize, usize, String)>) -> String {
+    edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+    let mut out = text.to_string();
+    for (start, end, replacement) in edits {
+        out.replace_range(start..end, &replacement);
+    }
+    out
+}
+
+fn describe(path: &[String]) -> String {
+    if path.is_empty() {
+        "The config root".to_string()
+    } else {
+        format!("`{}`", path.join("."))
+    }
+}
+
+/// Set the value at a key path, creating missing objects along the way
+pub fn set(text: &str, path: &[String], value: &Value) -> Result<String, ConfigError> {
+    if path.is_empty() {
+        return Err(ConfigError::InvalidEdit("Empty key path".to_string()));
+    }
+    if is_blank(text) {
+        let bom = if text.starts_with(BOM) { BOM } else { "" };
+        return Ok(format!("{}{}", bom, set("{\n}\n", path, value)?));
+    }
+
+    let root = parse_tree(text)?;
+    let unit = indent_unit(text);
+    let mut node = &root;
+
+    for (i, key) in path.iter().enumerate() {
+        let Node::Object { members, .. } = node else {
+            return Err(ConfigError::InvalidEdit(format!(
+                "{} is not an object",
+                describe(&path[..i])
+            )));
+        };
+
+        match members.iter().rev().find(|m| &m.key == key) {
+            Some(member) if i + 1 == path.len() => {
+                let rendered = render(value, &unit, line_indent(text, member.start));
+                return Ok(splice(
+                    text,
+                    vec![(member.value_start, member.value.end(), rendered)],
+                ));
+            }
+            Some(member) => node = &member.value,
+            None => {
+                let nested = path[i + 1..]
+                    .iter()
+                    .rev()
+                    .fold(value.clone(), |inner, k| serde_json::json!({ k: inner }));
+                return Ok(insert(text, node, key, &nested, &unit));
+            }
+        }
+    }
+
+    unreachable!("the loop returns on the last key")
+}
+
+fn insert(text: &str, object: &Node, key: &str, value: &Value, unit: &str) -> String {
+    let Node::Object {
+        start,
+        end,
+        members,
+    } = object
+    else {
+        unreachable!("insert is only called on objects");
+    };
+
+    let outer = line_indent(text, *start);
+    let Some(last) = members.last() else {
+        let inner = format!("{}{}", outer, unit);
+        let entry = format!(
+            "\n{}{}: {}",
+            inner,
+            render_key(key),
+            render(value, unit, &inner)
+        );
+        // Replace blank space between the braces, but keep any comments inside
+        let body = (*start + 1, *end - 1);
+        return if text[body.0..body.1].trim().is_empty() {
+            splice(
+                text,
+                vec![(body.0, body.1, format!("{}\n{}", entry, outer))],
+            )
+        } else {
+            splice(text, vec![(body.0, body.0, entry)])
+        };
+    };
+
+    let first = &members[0];
+    let inline = !text[*start..first.start].contains('\n');
+    let last_end = last.value.end();
+
+    if inline {
+        let entry = format!(
+            "{}: {}",
+            render_key(key),
+            serde_json::to_string(value).unwrap_or_default()
+        );
+        return match last.comma {
+            Some(comma) => splice(text, vec![(comma + 1, comma + 1, format!(" {},", entry))]),
+            None => splice(text, vec![(last_end, last_end, format!(", {}", entry))]),
+        };
+    }
+
+    let indent = line_indent(text, first.start);
+    let entry = format!(
+        "\n{}{}: {}",
+        indent,
+        render_key(key),
+        render(value, unit, indent)
+    );
+    match last.comma {
+        // Keep the trailing-comma style
+        Some(comma) => {
+            let at = end_of_line_content(text, comma + 1);
+            splice(text, vec![(at, at, format!("{},", entry))])
+        }
+        None => {
+            // One edit, so the comma can't end up after the entry when there's no comment
+            let at = end_of_line_content(text, last_end);
+            splice(
+                text,
+                vec![(last_end, at, format!(",{}{}", &text[last_end..at], entry))],
+            )
+        }
+    }
+}
+
+/// Remove the key at a key path; missing keys are left as they are
+pub fn remove(text: &str, path: &[String]) -> Result<String, ConfigError> {
+    let Some((key, parents)) = path.split_last() else {
+        return Err(ConfigError::InvalidEdit("Empty key path".to_string()));
+    };
+    if is_blank(text) {
+        return Ok(text.to_string());
+    }
+
+    let root = parse_tree(text)?;
+    let mut node = &root;
+    for parent in parents {
+        let Node::Object { members, .. } = node else {
+            return Ok(text.to_string());
+        };
+        match members.iter().rev().find(|m| &m.key == parent) {
+            Some(member) => node = &member.value,
+            None => return Ok(text.to_string()),
+        }
+    }
+
+    let Node::Object { members, .. } = node else {
+        return Ok(text.to_string());
+    };
+    let Some(index) = members.iter().rposition(|m| &m.key == key) else {
+        return Ok(text.to_string());
+    };
+
+    let member = &members[index];
+    let mut start = member.start;
+    let mut end = member.comma.map(|c| c + 1).unwrap_or(member.value.end());
+    let own_line = text[line_start(text, start)..start].trim().is_empty();
+
+    if own_line {
+        // Drop the whole line, including a trailing comment about the key
+        start = line_start(text, start);
+        end = end_of_line_content(text, end);
+        if text[end..].starts_with("\r\n") {
+            end += 2;
+        } else if text[end..].starts_with('\n') {
+            end += 1;
+        }
+    } else {
+        end += text[end..].len() - text[end..].trim_start_matches([' ', '\t']).len();
+    }
+
+    // The last member has no comma of its own, so drop the one before it
+    let previous_comma = match index {
+        0 => None,
+        _ if member.comma.is_some() => None,
+        _ => members[index - 1].comma,
+    };
+
+    let edits = match previous_comma {
+        Some(comma) if !own_line => vec![(comma, member.value.end(), String::new())],
+        Some(comma) => vec![
+            (start, end, String::new()),
+            (comma, comma + 1, String::new()),
+        ],
+        None => vec![(start, end, String::new())],
+    };
+
+    Ok(splice(text, edits))
+}
+
+/// Apply edits in order
+pub fn apply(text: &str, edits: &[ConfigEdit]) -> Result<String, ConfigError> {
+    edits
+        .iter()
+        .try_fold(text.to_string(), |text, edit| match &edit.value {
+            Some(value) => set(&text, &edit.path, value),
+            None => remove(&text, &edit.path),
+        })
+}
+
+// --------------------------------------------
+// Commands
+// --------------------------------------------
+
+fn read_text(path: &std::path::Path) -> Result<String, ConfigError> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_validated(path: &std::path::Path, text: &str) -> Result<(), ConfigError> {
+    validate(&parse(text)?)?;
+    crate::storage::write_atomic(path, text.as_bytes())?;
+    Ok(())
+}
+
+/// Read an opencode.json/jsonc file, comments and trailing commas allowed
+#[tauri::command]
+pub fn read_opencode_config(app_handle: AppHandle, path: String) -> Result<Value, ConfigError> {
+    let path = crate::sandbox::resolve(&app_handle, &path)?;
+    let text = read_text(&path)?;
+    if is_blank(&text) {
+        return Ok(Value::Object(Default::default()));
+    }
+    parse(&text)
+}
+
+/// Apply key-path edits to the config file, keeping comments and formatting.
+/// Returns the new file content.
+#[tauri::command]
+pub fn edit_opencode_config(
+    app_handle: AppHandle,
+    path: String,
+    edits: Vec<ConfigEdit>,
+) -> Result<String, ConfigError> {
+    let path = crate::sandbox::resolve(&app_handle, &path)?;
+    let text = apply(&read_text(&path)?, &edits)?;
+    write_validated(&path, &text)?;
+    Ok(text)
+}
+
+/// Replace the config file with `content` after checking it against the schema
+#[tauri::command]
+pub fn save_opencode_config(
+    app_handle: AppHandle,
+    path: String,
+    content: String,
+) -> Result<(), ConfigError> {
+    let path = crate::sandbox::resolve(&app_handle, &path)?;
+    write_validated(&path, &content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn path(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|k| k.to_string()).collect()
+    }
+
+    /// Set a value and check the result still parses to what was asked for
+    fn set_ok(text: &str, keys: &[&str], value: Value) -> String {
+        let edited = set(text, &path(keys), &value).unwrap();
+        let parsed = parse(&edited).unwrap_or_else(|e| panic!("{}\n---\n{}", e, edited));
+        let got = keys.iter().fold(&parsed, |v, k| &v[*k]);
+        assert_eq!(got, &value, "{}", edited);
+        edited
+    }
+
+    #[test]
+    fn parses_comments_and_trailing_commas() {
+        let text = r#"{
+  // line comment
+  "model": "a", /* block
+  comment */
+  "list": [1, 2,],
+  "url": "http://example.com", // not a comment inside a string
+}"#;
+        assert_eq!(
+            parse(text).unwrap(),
+            json!({ "model": "a", "list": [1, 2], "url": "http://example.com" })
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_location() {
+        let Err(ConfigError::Syntax(message)) = parse("{\n  \"a\": 1\n  \"b\": 2\n}") else {
+            panic!("expected a syntax error");
+        };
+        assert!(message.contains("3:3"), "{}", message);
+        assert!(matches!(parse("{ /* open"), Err(ConfigError::Syntax(_))));
+        assert!(matches!(parse("{} {}"), Err(ConfigError::Syntax(_))));
+
+        // A lone `/` used to stall the tokenizer
+        let Err(ConfigError::Syntax(message)) = parse("{\"a\": 1 / 2}") else {
+            panic!("expected a syntax error");
+        };
+        assert!(message.contains("1:9"), "{}", message);
+    }
+
+    #[test]
+    fn refuses_deep_nesting() {
+        let deep = "[".repeat(100_000);
+        let Err(ConfigError::Syntax(message)) = parse(&deep) else {
+            panic!("expected a syntax error");
+        };
+        assert!(message.contains("Nested too deeply"), "{}", message);
+
+        let nested = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        assert!(parse(&nested).is_ok());
+    }
+
+    #[test]
+    fn inserts_into_empty_objects() {
+        assert_eq!(
+            set_ok("", &["theme"], json!("dark")),
+            "{\n  \"theme\": \"dark\"\n}\n"
+        );
+        assert_eq!(
+            set_ok("{}", &["theme"], json!("dark")),
+            "{\n  \"theme\": \"dark\"\n}"
+        );
+        let commented = set_ok("{\n  // keep me\n}", &["theme"], json!("dark"));
+        assert!(commented.contains("// keep me"), "{}", commented);
+    }
+
+    #[test]
+    fn inserts_into_single_line_objects() {
+        assert_eq!(
+            set_ok(r#"{ "model": "a" }"#, &["theme"], json!("dark")),
+            r#"{ "model": "a", "theme": "dark" }"#
+        );
+        assert_eq!(
+            set_ok(r#"{ "model": "a", }"#, &["theme"], json!("dark")),
+            r#"{ "model": "a", "theme": "dark", }"#
+        );
+    }
+
+    #[test]
+    fn inserts_into_multi_line_objects() {
+        assert_eq!(
+            set_ok("{\n  \"model\": \"a\"\n}", &["theme"], json!("dark")),
+            "{\n  \"model\": \"a\",\n  \"theme\": \"dark\"\n}"
+        );
+        assert_eq!(
+            set_ok("{\n  \"model\": \"a\",\n}", &["theme"], json!("dark")),
+            "{\n  \"model\": \"a\",\n  \"theme\": \"dark\",\n}"
+        );
+        // The comma goes before a trailing comment, the entry after it
+        assert_eq!(
+            set_ok("{\n  \"model\": \"a\" // why\n}", &["theme"], json!("dark")),
+            "{\n  \"model\": \"a\", // why\n  \"theme\": \"dark\"\n}"
+        );
+    }
+
+    #[test]
+    fn inserts_after_a_trailing_block_comment() {
+        assert_eq!(
+            set_ok(
+                "{\n  \"model\": \"a\" /* why */\n}",
+                &["theme"],
+                json!("dark")
+            ),
+            "{\n  \"model\": \"a\", /* why */\n  \"theme\": \"dark\"\n}"
+        );
+        assert_eq!(
+            set_ok(
+                "{\n  \"model\": \"a\", /* why */ // and how\n}",
+                &["theme"],
+                json!("dark")
+            ),
+            "{\n  \"model\": \"a\", /* why */ // and how\n  \"theme\": \"dark\",\n}"
+        );
+        // A block comment running onto the next lines isn't split either
+        let open = "{\n  \"model\": \"a\" /* why\n  */\n}";
+        let edited = set_ok(open, &["theme"], json!("dark"));
+        assert!(edited.contains("/* why\n  */"), "{}", edited);
+    }
+
+    #[test]
+    fn keeps_a_byte_order_mark() {
+        let text = "\u{feff}{\n  \"model\": \"a\"\n}";
+        assert_eq!(parse(text).unwrap(), json!({ "model": "a" }));
+
+        let edited = set_ok(text, &["theme"], json!("dark"));
+        assert_eq!(
+            edited,
+            "\u{feff}{\n  \"model\": \"a\",\n  \"theme\": \"dark\"\n}"
+        );
+        assert_eq!(remove(&edited, &path(&["theme"])).unwrap(), text);
+        assert_eq!(
+            set_ok("\u{feff}", &["theme"], json!("dark")),
+            "\u{feff}{\n  \"theme\": \"dark\"\n}\n"
+        );
+        // Only at the very start
+        assert!(matches!(parse("{}\u{feff}"), Err(ConfigError::Syntax(_))));
+    }
+
+    #[test]
+    fn creates_nested_paths() {
+        let text = "{\n  // servers\n  \"mcp\": {\n    \"fs\": {\n      \"type\": \"local\"\n    }\n  }\n}";
+        let edited = set_ok(text, &["mcp", "fs", "enabled"], json!(false));
+        assert!(edited.contains("// servers"), "{}", edited);
+        assert!(
+            edited.contains("\"type\": \"local\",\n      \"enabled\": false\n"),
+            "{}",
+            edited
+        );
+
+        let edited = set_ok(text, &["mcp", "web", "enabled"], json!(true));
+        assert_eq!(parse(&edited).unwrap()["mcp"]["fs"]["type"], "local");
+
+        assert!(matches!(
+            set("{ \"model\": \"a\" }", &path(&["model", "x"]), &json!(1)),
+            Err(ConfigError::InvalidEdit(_))
+        ));
+    }
+
+    #[test]
+    fn replaces_existing_values_in_place() {
+        assert_eq!(
+            set_ok("{\n  \"model\": \"a\", // why\n}", &["model"], json!("b")),
+            "{\n  \"model\": \"b\", // why\n}"
+        );
+    }
+
+    #[test]
+    fn removes_keys_and_their_commas() {
+        let text = "{\n  \"a\": 1,\n  \"b\": 2 // note\n}";
+        assert_eq!(remove(text, &path(&["b"])).unwrap(), "{\n  \"a\": 1\n}");
+        assert_eq!(
+            remove(text, &path(&["a"])).unwrap(),
+            "{\n  \"b\": 2 // note\n}"
+        );
+        assert_eq!(
+            remove(r#"{ "a": 1, "b": 2 }"#, &path(&["b"])).unwrap(),
+            r#"{ "a": 1 }"#
+        );
+        assert_eq!(remove(text, &path(&["missing", "x"])).unwrap(), text);
+    }
+}
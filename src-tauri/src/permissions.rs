@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+// Pending permission requests in arrival order, keyed by request id
+static QUEUE: Mutex<VecDeque<PermissionData>> = Mutex::new(VecDeque::new());
+
+// Ids removed recently, so the same request arriving again from the webview
+// and the event stream isn't queued twice
+static RECENTLY_REMOVED: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+const RECENTLY_REMOVED_LIMIT: usize = 64;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct PermissionData {
+    /// Request id, filled from `request.id` when queued
+    #[serde(default)]
+    pub id: String,
+    pub request: serde_json::Value,
+    #[serde(rename = "sessionTitle")]
+    pub session_title: String,
+    #[serde(rename = "instanceUrl")]
+    pub instance_url: String,
+}
+
+impl PermissionData {
+    /// The queue key: `id` if set, otherwise `request.id`
+    pub fn key(&self) -> String {
+        if !self.id.is_empty() {
+            return self.id.clone();
+        }
+        self.request
+            .get("id")
+            .and_then(|id| id.as_str())
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
+/// Queue a request, returns false if it was already queued (it is updated in place),
+/// was answered recently or has no id
+pub fn push(mut data: PermissionData) -> bool {
+    data.id = data.key();
+    if data.id.is_empty() {
+        return false;
+    }
+
+    if RECENTLY_REMOVED
+        .lock()
+        .map(|recent| recent.contains(&data.id))
+        .unwrap_or(false)
+    {
+        return false;
+    }
+
+    let Ok(mut queue) = QUEUE.lock() else {
+        return false;
+    };
+
+    if let Some(existing) = queue.iter_mut().find(|p| p.id == data.id) {
+        *existing = data;
+        return false;
+    }
+
+    queue.push_back(data);
+    true
+}
+
+/// Remove a request by id
+pub fn remove(id: &str) -> Option<PermissionData> {
+    if let Ok(mut recent) = RECENTLY_REMOVED.lock() {
+        if !recent.iter().any(|r| r == id) {
+            recent.push_back(id.to_string());
+            if recent.len() > RECENTLY_REMOVED_LIMIT {
+                recent.pop_front();
+            }
+        }
+    }
+
+    let mut queue = QUEUE.lock().ok()?;
+    let index = queue.iter().position(|p| p.id == id)?;
+    queue.remove(index)
+}
+
+pub fn get(id: &str) -> Option<PermissionData> {
+    let queue = QUEUE.lock().ok()?;
+    queue.iter().find(|p| p.id == id).cloned()
+}
+
+/// The oldest pending request, shown in the popup
+pub fn front() -> Option<PermissionData> {
+    QUEUE.lock().ok()?.front().cloned()
+}
+
+pub fn list() -> Vec<PermissionData> {
+    QUEUE
+        .lock()
+        .map(|queue| queue.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn len() -> usize {
+    QUEUE.lock().map(|queue| queue.len()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The queue is global, so each test uses its own ids
+    fn data(id: &str, title: &str) -> PermissionData {
+        PermissionData {
+            id: String::new(),
+            request: serde_json::json!({ "id": id }),
+            session_title: title.to_string(),
+            instance_url: "http://127.0.0.1:4096".to_string(),
+        }
+    }
+
+    fn queued(prefix: &str) -> Vec<(String, String)> {
+        list()
+            .into_iter()
+            .filter(|p| p.id.starts_with(prefix))
+            .map(|p| (p.id, p.session_title))
+            .collect()
+    }
+
+    #[test]
+    fn keeps_arrival_order() {
+        for id in ["order-b", "order-a", "order-c"] {
+            assert!(push(data(id, "")));
+        }
+        let ids: Vec<String> = queued("order-").into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec!["order-b", "order-a", "order-c"]);
+    }
+
+    #[test]
+    fn updates_duplicates_in_place() {
+        assert!(push(data("dedupe-1", "first")));
+        assert!(push(data("dedupe-2", "")));
+        assert!(!push(data("dedupe-1", "second")));
+        assert_eq!(
+            queued("dedupe-"),
+            vec![
+                ("dedupe-1".to_string(), "second".to_string()),
+                ("dedupe-2".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn requests_without_an_id_are_not_queued() {
+        let mut missing = data("", "first");
+        missing.request = serde_json::json!({});
+        assert!(!push(missing.clone()));
+        assert!(!push(missing));
+        assert!(get("").is_none());
+    }
+
+    #[test]
+    fn removed_requests_are_not_queued_again() {
+        assert!(push(data("remove-1", "")));
+        assert!(push(data("remove-2", "")));
+        assert_eq!(
+            remove("remove-1").map(|p| p.id).as_deref(),
+            Some("remove-1")
+        );
+        assert!(remove("remove-1").is_none());
+        assert!(get("remove-1").is_none());
+
+        assert!(!push(data("remove-1", "")));
+        assert_eq!(queued("remove-").len(), 1);
+    }
+}
@@ -0,0 +1,484 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+use crate::permissions::PermissionData;
+
+// The rules from the settings, compiled by `load`
+static POLICY: RwLock<Policy> = RwLock::new(Policy { rules: Vec::new() });
+
+/// What to do with a permission request
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    Allow,
+    Deny,
+    Ask,
+}
+
+/// A user-defined auto-approval rule, stored in `AppSettings.permission_rules`
+///
+/// Every field that is set must match. Globs use `*`, `?` and `**`.
+/// `pattern` is matched against the request patterns (e.g. the bash command),
+/// `path` against the file the tool touches and `directory` against the
+/// instance's project directory. In `path` and `directory`, `*` stays within
+/// one path segment and `..` is resolved before matching. An `allow` rule
+/// needs every pattern/path of the request to match, `deny` and `ask` rules
+/// need just one.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct PermissionRule {
+    #[serde(default)]
+    pub tool: Option<String>,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub directory: Option<String>,
+    pub action: RuleAction,
+}
+
+/// Result of evaluating a request against the rules
+#[derive(Clone, PartialEq, Debug)]
+pub struct Decision {
+    pub action: RuleAction,
+    /// Index of the rule that matched, `None` when falling back to asking
+    pub rule: Option<usize>,
+}
+
+/// Rules with their globs compiled once, in settings order
+pub struct Policy {
+    rules: Vec<CompiledRule>,
+}
+
+struct CompiledRule {
+    /// Position in `AppSettings.permission_rules`
+    index: usize,
+    action: RuleAction,
+    tool: Option<GlobMatcher>,
+    pattern: Option<GlobMatcher>,
+    path: Option<GlobMatcher>,
+    directory: Option<GlobMatcher>,
+}
+
+/// `literal_separator` keeps `*` from crossing `/`, for globs over paths
+fn compile(glob: &str, literal_separator: bool) -> Result<GlobMatcher, globset::Error> {
+    GlobBuilder::new(glob)
+        .literal_separator(literal_separator)
+        .build()
+        .map(|g| g.compile_matcher())
+}
+
+impl CompiledRule {
+    fn new(index: usize, rule: &PermissionRule) -> Result<Self, globset::Error> {
+        let optional = |glob: &Option<String>, literal_separator| {
+            glob.as_deref()
+                .map(|g| compile(g, literal_separator))
+                .transpose()
+        };
+        Ok(Self {
+            index,
+            action: rule.action,
+            tool: optional(&rule.tool, false)?,
+            pattern: optional(&rule.pattern, false)?,
+            path: optional(&rule.path, true)?,
+            directory: optional(&rule.directory, true)?,
+        })
+    }
+
+    fn matches(
+        &self,
+        tool: &str,
+        patterns: &[Option<String>],
+        paths: &[Option<String>],
+        directory: Option<&str>,
+    ) -> bool {
+        let require_all = self.action == RuleAction::Allow;
+
+        if let Some(m) = &self.tool {
+            if !m.is_match(tool) {
+                return false;
+            }
+        }
+        if let Some(m) = &self.pattern {
+            if !matches_set(m, patterns, require_all) {
+                return false;
+            }
+        }
+        if let Some(m) = &self.path {
+            if !matches_set(m, paths, require_all) {
+                return false;
+            }
+        }
+        if let Some(m) = &self.directory {
+            if !directory.is_some_and(|d| m.is_match(d)) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl Policy {
+    /// Compile the rules; one with an invalid glob is logged here and never matches
+    pub fn new(rules: &[PermissionRule]) -> Self {
+        let rules = rules
+            .iter()
+            .enumerate()
+            .filter_map(|(index, rule)| match CompiledRule::new(index, rule) {
+                Ok(compiled) => Some(compiled),
+                Err(e) => {
+                    log::warn!("ignoring rule {} with an invalid glob: {}", index, e);
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Evaluate a request, the first matching rule wins
+    pub fn evaluate(&self, data: &PermissionData, directory: &str) -> Decision {
+        let tool = data
+            .request
+            .get("permission")
+            .and_then(|p| p.as_str())
+            .unwrap_or_default();
+        let patterns = string_list(data.request.get("patterns"));
+        let directory = normalize(directory);
+        let paths: Vec<Option<String>> = request_paths(&data.request, &patterns)
+            .iter()
+            .map(|p| resolve(p, directory.as_deref()))
+            .collect();
+        let patterns: Vec<Option<String>> = patterns.into_iter().map(Some).collect();
+
+        for rule in &self.rules {
+            if rule.matches(tool, &patterns, &paths, directory.as_deref()) {
+                return Decision {
+                    action: rule.action,
+                    rule: Some(rule.index),
+                };
+            }
+        }
+
+        Decision {
+            action: RuleAction::Ask,
+            rule: None,
+        }
+    }
+}
+
+/// Compile the rules from the settings, at startup and whenever they change
+pub fn load(rules: &[PermissionRule]) {
+    let policy = Policy::new(rules);
+    if let Ok(mut current) = POLICY.write() {
+        *current = policy;
+    }
+}
+
+/// Evaluate a request against the loaded rules
+pub fn evaluate(data: &PermissionData, directory: &str) -> Decision {
+    match POLICY.read() {
+        Ok(policy) => policy.evaluate(data, directory),
+        Err(_) => Decision {
+            action: RuleAction::Ask,
+            rule: None,
+        },
+    }
+}
+
+/// Whether a path glob matches `path`, with the same rules as `PermissionRule.path`
+pub fn path_matches(glob: &str, path: &str) -> bool {
+    match compile(glob, true) {
+        Ok(m) => normalize(path).is_some_and(|p| m.is_match(p)),
+        Err(e) => {
+            log::warn!("ignoring invalid glob {:?}: {}", glob, e);
+            false
+        }
+    }
+}
+
+/// Resolve `.` and `..` without touching the disk
+///
+/// `..` at the root stays there, as the OS does. A relative path that climbs
+/// above its start can't be resolved and gives `None`, which no glob matches.
+fn normalize(path: &str) -> Option<String> {
+    let mut out = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() && !out.has_root() {
+                    return None;
+                }
+            }
+            component => out.push(component),
+        }
+    }
+    Some(out.to_string_lossy().into_owned())
+}
+
+/// Normalize a request path, joining a relative one onto the instance directory
+/// first so it meets the same absolute globs as its absolute form
+fn resolve(path: &str, directory: Option<&str>) -> Option<String> {
+    match directory {
+        Some(directory) if Path::new(path).is_relative() => {
+            normalize(&Path::new(directory).join(path).to_string_lossy())
+        }
+        _ => normalize(path),
+    }
+}
+
+/// An empty set never matches, so a rule with a pattern can't approve a
+/// request without one
+fn matches_set(m: &GlobMatcher, values: &[Option<String>], require_all: bool) -> bool {
+    if values.is_empty() {
+        return false;
+    }
+    let is_match = |v: &Option<String>| v.as_deref().is_some_and(|v| m.is_match(v));
+    if require_all {
+        values.iter().all(is_match)
+    } else {
+        values.iter().any(is_match)
+    }
+}
+
+fn string_list(value: Option<&serde_json::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|i| i.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Files the request touches, from metadata when present, otherwise the patterns
+fn request_paths(request: &serde_json::Value, patterns: &[String]) -> Vec<String> {
+    let metadata = request.get("metadata");
+    for key in ["filepath", "filePath", "path"] {
+        if let Some(path) = metadata.and_then(|m| m.get(key)).and_then(|p| p.as_str()) {
+            return vec![path.to_string()];
+        }
+    }
+    patterns.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(action: RuleAction) -> PermissionRule {
+        PermissionRule {
+            tool: None,
+            pattern: None,
+            path: None,
+            directory: None,
+            action,
+        }
+    }
+
+    fn request(permission: &str, patterns: &[&str]) -> PermissionData {
+        PermissionData {
+            id: "per_1".to_string(),
+            request: json!({ "id": "per_1", "permission": permission, "patterns": patterns }),
+            session_title: "Session".to_string(),
+            instance_url: "http://127.0.0.1:4096".to_string(),
+        }
+    }
+
+    fn with_path(mut data: PermissionData, path: &str) -> PermissionData {
+        data.request["metadata"] = json!({ "filepath": path });
+        data
+    }
+
+    fn decide(rules: &[PermissionRule], data: &PermissionData, directory: &str) -> Decision {
+        Policy::new(rules).evaluate(data, directory)
+    }
+
+    #[test]
+    fn asks_without_a_matching_rule() {
+        let decision = decide(&[], &request("bash", &["ls"]), "/proj");
+        assert_eq!(decision.action, RuleAction::Ask);
+        assert_eq!(decision.rule, None);
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = [
+            PermissionRule {
+                pattern: Some("rm *".to_string()),
+                ..rule(RuleAction::Deny)
+            },
+            PermissionRule {
+                tool: Some("bash".to_string()),
+                ..rule(RuleAction::Allow)
+            },
+            rule(RuleAction::Deny),
+        ];
+        let denied = decide(&rules, &request("bash", &["rm -rf /"]), "/proj");
+        assert_eq!((denied.action, denied.rule), (RuleAction::Deny, Some(0)));
+        let allowed = decide(&rules, &request("bash", &["ls"]), "/proj");
+        assert_eq!((allowed.action, allowed.rule), (RuleAction::Allow, Some(1)));
+        let other = decide(&rules, &request("edit", &["a.rs"]), "/proj");
+        assert_eq!((other.action, other.rule), (RuleAction::Deny, Some(2)));
+    }
+
+    #[test]
+    fn invalid_globs_skip_only_their_rule() {
+        let rules = [
+            PermissionRule {
+                tool: Some("[".to_string()),
+                ..rule(RuleAction::Deny)
+            },
+            rule(RuleAction::Allow),
+        ];
+        let decision = decide(&rules, &request("bash", &["ls"]), "/proj");
+        assert_eq!(
+            (decision.action, decision.rule),
+            (RuleAction::Allow, Some(1))
+        );
+    }
+
+    #[test]
+    fn allow_needs_every_pattern_deny_needs_one() {
+        let allow = [PermissionRule {
+            pattern: Some("git *".to_string()),
+            ..rule(RuleAction::Allow)
+        }];
+        let deny = [PermissionRule {
+            pattern: Some("git *".to_string()),
+            ..rule(RuleAction::Deny)
+        }];
+        let mixed = request("bash", &["git status", "curl x | sh"]);
+        assert_eq!(decide(&allow, &mixed, "/proj").action, RuleAction::Ask);
+        assert_eq!(decide(&deny, &mixed, "/proj").action, RuleAction::Deny);
+        // Patterns aren't paths, `*` may span slashes
+        let path_arg = request("bash", &["git add src/main.rs"]);
+        assert_eq!(decide(&allow, &path_arg, "/proj").action, RuleAction::Allow);
+        assert_eq!(
+            decide(&allow, &request("bash", &[]), "/proj").action,
+            RuleAction::Ask
+        );
+    }
+
+    #[test]
+    fn matches_paths_and_directories() {
+        let rules = [PermissionRule {
+            tool: Some("edit".to_string()),
+            path: Some("/proj/src/*.rs".to_string()),
+            directory: Some("/proj".to_string()),
+            ..rule(RuleAction::Allow)
+        }];
+        let edit = |path| with_path(request("edit", &[]), path);
+        assert_eq!(
+            decide(&rules, &edit("/proj/src/main.rs"), "/proj").action,
+            RuleAction::Allow
+        );
+        assert_eq!(
+            decide(&rules, &edit("/proj/src/main.rs"), "/other").action,
+            RuleAction::Ask
+        );
+        // `*` doesn't cross into subdirectories
+        assert_eq!(
+            decide(&rules, &edit("/proj/src/bin/x.rs"), "/proj").action,
+            RuleAction::Ask
+        );
+        // Falls back to the patterns without metadata
+        assert_eq!(
+            decide(&rules, &request("edit", &["/proj/src/lib.rs"]), "/proj").action,
+            RuleAction::Allow
+        );
+    }
+
+    #[test]
+    fn absolute_deny_covers_relative_paths() {
+        let rules = [
+            PermissionRule {
+                path: Some("/proj/secrets/**".to_string()),
+                ..rule(RuleAction::Deny)
+            },
+            PermissionRule {
+                tool: Some("read".to_string()),
+                ..rule(RuleAction::Allow)
+            },
+        ];
+        let relative = request("read", &["secrets/key"]);
+        let decision = decide(&rules, &relative, "/proj");
+        assert_eq!(
+            (decision.action, decision.rule),
+            (RuleAction::Deny, Some(0))
+        );
+        let dotted = with_path(request("read", &[]), "./src/../secrets/key");
+        assert_eq!(decide(&rules, &dotted, "/proj").action, RuleAction::Deny);
+        let elsewhere = request("read", &["src/main.rs"]);
+        assert_eq!(
+            decide(&rules, &elsewhere, "/proj").action,
+            RuleAction::Allow
+        );
+    }
+
+    #[test]
+    fn traversal_does_not_escape_path_globs() {
+        let rules = [PermissionRule {
+            path: Some("/proj/**".to_string()),
+            ..rule(RuleAction::Allow)
+        }];
+        let read = |path| with_path(request("read", &[]), path);
+        assert_eq!(
+            decide(&rules, &read("/proj/a/b.txt"), "/proj").action,
+            RuleAction::Allow
+        );
+        assert_eq!(
+            decide(&rules, &read("/proj/./a/../b.txt"), "/proj").action,
+            RuleAction::Allow
+        );
+        assert_eq!(
+            decide(&rules, &read("/proj/../../etc/passwd"), "/proj").action,
+            RuleAction::Ask
+        );
+        assert_eq!(
+            decide(&rules, &read("/proj/../etc"), "/proj").action,
+            RuleAction::Ask
+        );
+        // Relative paths resolve against the instance directory
+        assert_eq!(
+            decide(&rules, &read("a/b.txt"), "/proj").action,
+            RuleAction::Allow
+        );
+        assert_eq!(
+            decide(&rules, &read("../other/x"), "/proj").action,
+            RuleAction::Ask
+        );
+
+        let directory = [PermissionRule {
+            directory: Some("/proj/*".to_string()),
+            ..rule(RuleAction::Allow)
+        }];
+        let any = request("bash", &["ls"]);
+        assert_eq!(
+            decide(&directory, &any, "/proj/app").action,
+            RuleAction::Allow
+        );
+        assert_eq!(
+            decide(&directory, &any, "/proj/app/nested").action,
+            RuleAction::Ask
+        );
+        assert_eq!(
+            decide(&directory, &any, "/proj/app/../../etc").action,
+            RuleAction::Ask
+        );
+    }
+
+    #[test]
+    fn normalizes_lexically() {
+        assert_eq!(normalize("/a/./b/../c").as_deref(), Some("/a/c"));
+        assert_eq!(normalize("/../etc").as_deref(), Some("/etc"));
+        assert_eq!(normalize("a/../b").as_deref(), Some("b"));
+        assert_eq!(normalize("a/../../b"), None);
+    }
+}
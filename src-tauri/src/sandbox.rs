@@ -0,0 +1,238 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+
+/// Error returned by the file commands, serialized as `{ kind, message }`
+#[derive(serde::Serialize, Debug)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum FileAccessError {
+    /// The path is not inside an instance directory or a config directory
+    OutsideAllowedRoots(String),
+    /// The path is relative, contains `..` or points through a dangling symlink
+    InvalidPath(String),
+    Io(String),
+}
+
+impl std::fmt::Display for FileAccessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileAccessError::OutsideAllowedRoots(path) => {
+                write!(f, "Access to {} is not allowed", path)
+            }
+            FileAccessError::InvalidPath(path) => write!(f, "Invalid path: {}", path),
+            FileAccessError::Io(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for FileAccessError {
+    fn from(e: std::io::Error) -> Self {
+        FileAccessError::Io(e.to_string())
+    }
+}
+
+/// OpenCode's global config dir (`$XDG_CONFIG_HOME/opencode` or `~/.config/opencode`)
+pub fn opencode_config_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    if let Some(xdg) = std::env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(xdg).join("opencode"));
+    }
+    app_handle
+        .path()
+        .home_dir()
+        .ok()
+        .map(|home| home.join(".config").join("opencode"))
+}
+
+/// Directories the webview may read and write: known instance directories,
+/// the OpenCode config dir and OpenTray's own config dir
+pub fn allowed_roots(app_handle: &AppHandle) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = crate::discovery::instances()
+        .into_iter()
+        .map(|i| PathBuf::from(i.directory))
+        .collect();
+    roots.extend(opencode_config_dir(app_handle));
+    roots.extend(app_handle.path().app_config_dir().ok());
+
+    roots
+        .iter()
+        .filter_map(|root| normalize(root).ok())
+        .collect()
+}
+
+// Where desktops keep their own notification sounds
+const SYSTEM_SOUND_DIRS: &[&str] = &[
+    "/usr/share/sounds",
+    "/System/Library/Sounds",
+    "/Library/Sounds",
+    "C:\\Windows\\Media",
+];
+
+/// Directories custom notification sounds may be read from: the allowed roots,
+/// the user's audio directory and the system sound directories
+pub fn sound_roots(app_handle: &AppHandle) -> Vec<PathBuf> {
+    let mut roots = allowed_roots(app_handle);
+    let extra = app_handle
+        .path()
+        .audio_dir()
+        .ok()
+        .into_iter()
+        .chain(SYSTEM_SOUND_DIRS.iter().map(PathBuf::from));
+    roots.extend(extra.filter_map(|root| normalize(&root).ok()));
+    roots
+}
+
+/// Resolve a path from the webview, rejecting anything outside the allowed roots
+pub fn resolve(app_handle: &AppHandle, path: &str) -> Result<PathBuf, FileAccessError> {
+    resolve_within(path, &allowed_roots(app_handle))
+}
+
+/// Resolve a path, rejecting anything outside `roots` (which must be normalized)
+pub fn resolve_within(path: &str, roots: &[PathBuf]) -> Result<PathBuf, FileAccessError> {
+    let resolved = normalize(Path::new(path))?;
+    if roots.iter().any(|root| resolved.starts_with(root)) {
+        Ok(resolved)
+    } else {
+        Err(FileAccessError::OutsideAllowedRoots(path.to_string()))
+    }
+}
+
+/// Canonicalize a path that may not exist yet
+///
+/// The longest existing prefix is canonicalized (resolving symlinks) and the
+/// missing components are appended, which must be plain names.
+fn normalize(path: &Path) -> Result<PathBuf, FileAccessError> {
+    let invalid = || FileAccessError::InvalidPath(path.display().to_string());
+
+    if !path.is_absolute() {
+        return Err(invalid());
+    }
+
+    let mut existing = path.to_path_buf();
+    let mut missing = Vec::new();
+    loop {
+        match existing.canonicalize() {
+            Ok(mut resolved) => {
+                for name in missing.iter().rev() {
+                    resolved.push(name);
+                }
+                return Ok(resolved);
+            }
+            Err(_) => {
+                // A symlink that can't be resolved could point anywhere
+                if existing.symlink_metadata().is_ok() {
+                    return Err(invalid());
+                }
+                // `file_name` is `None` for `..` and the filesystem root
+                let name = existing.file_name().ok_or_else(invalid)?.to_os_string();
+                missing.push(name);
+                existing.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A root directory to allow and a sibling outside it
+    fn dirs() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().canonicalize().unwrap();
+        let (root, outside) = (base.join("root"), base.join("outside"));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(root.join("src/main.rs"), "").unwrap();
+        std::fs::write(outside.join("secret"), "").unwrap();
+        (temp, root, outside)
+    }
+
+    fn within(path: &Path, root: &Path) -> Result<PathBuf, FileAccessError> {
+        resolve_within(path.to_str().unwrap(), &[root.to_path_buf()])
+    }
+
+    #[test]
+    fn allows_paths_inside_the_root() {
+        let (_temp, root, _) = dirs();
+        let file = root.join("src/main.rs");
+        assert_eq!(within(&file, &root).unwrap(), file);
+        assert_eq!(within(&root.join("src/./main.rs"), &root).unwrap(), file);
+    }
+
+    #[test]
+    fn rejects_relative_paths() {
+        let (_temp, root, _) = dirs();
+        assert!(matches!(
+            within(Path::new("src/main.rs"), &root),
+            Err(FileAccessError::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_parent_escapes() {
+        let (_temp, root, _) = dirs();
+        assert!(matches!(
+            within(&root.join("../outside/secret"), &root),
+            Err(FileAccessError::OutsideAllowedRoots(_))
+        ));
+        assert!(matches!(
+            within(&root.join("src/../../outside"), &root),
+            Err(FileAccessError::OutsideAllowedRoots(_))
+        ));
+        // `..` after a missing directory can't be resolved
+        assert!(matches!(
+            within(&root.join("missing/../../outside/secret"), &root),
+            Err(FileAccessError::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn appends_missing_leaf_paths() {
+        let (_temp, root, outside) = dirs();
+        assert_eq!(
+            within(&root.join("new/dir/file.json"), &root).unwrap(),
+            root.join("new/dir/file.json")
+        );
+        assert!(matches!(
+            within(&outside.join("new.json"), &root),
+            Err(FileAccessError::OutsideAllowedRoots(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinks_before_checking() {
+        let (_temp, root, outside) = dirs();
+        std::os::unix::fs::symlink(&outside, root.join("escape")).unwrap();
+        std::os::unix::fs::symlink(root.join("src"), root.join("inner")).unwrap();
+
+        assert!(matches!(
+            within(&root.join("escape/secret"), &root),
+            Err(FileAccessError::OutsideAllowedRoots(_))
+        ));
+        assert!(matches!(
+            within(&root.join("escape/new.json"), &root),
+            Err(FileAccessError::OutsideAllowedRoots(_))
+        ));
+        assert_eq!(
+            within(&root.join("inner/main.rs"), &root).unwrap(),
+            root.join("src/main.rs")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_dangling_symlinks() {
+        let (_temp, root, outside) = dirs();
+        std::os::unix::fs::symlink(outside.join("gone"), root.join("dangling")).unwrap();
+
+        assert!(matches!(
+            within(&root.join("dangling"), &root),
+            Err(FileAccessError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            within(&root.join("dangling/child"), &root),
+            Err(FileAccessError::InvalidPath(_))
+        ));
+    }
+}
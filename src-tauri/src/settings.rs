@@ -0,0 +1,564 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use notify_debouncer_mini::new_debouncer;
+use notify_debouncer_mini::notify::RecursiveMode;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::command::AppSettings;
+
+/// Version written by this build
+pub const CURRENT_VERSION: u32 = 2;
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades a version `n` file to version `n + 1`
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1, migrate_v1_to_v2];
+
+const _: () = assert!(MIGRATIONS.len() == CURRENT_VERSION as usize);
+
+/// v0 files have no `version` key. Older frontends could write `null` for
+/// unset values and an empty shortcut for "use the default"; both made the
+/// whole file fail to load.
+fn migrate_v0_to_v1(settings: &mut Map<String, Value>) {
+    settings.retain(|_, value| !value.is_null());
+    if settings
+        .get("global_shortcut")
+        .and_then(|v| v.as_str())
+        .is_some_and(|s| s.trim().is_empty())
+    {
+        settings.remove("global_shortcut");
+    }
+}
+
+/// v2 replaces the single `global_shortcut` with the `shortcuts` action map
+fn migrate_v1_to_v2(settings: &mut Map<String, Value>) {
+    let Some(Value::String(shortcut)) = settings.remove("global_shortcut") else {
+        return;
+    };
+    let shortcuts = settings
+        .entry("shortcuts")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Some(shortcuts) = shortcuts.as_object_mut() {
+        shortcuts
+            .entry("toggle_panel")
+            .or_insert(Value::String(shortcut));
+    }
+}
+
+fn version_of(settings: &Map<String, Value>) -> u32 {
+    settings
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .map(|v| v.min(u32::MAX as u64) as u32)
+        .unwrap_or(0)
+}
+
+/// Run the migrations from the file's version up to `CURRENT_VERSION`,
+/// returns whether anything ran. Files from a newer build are left alone.
+pub fn migrate(settings: &mut Map<String, Value>) -> bool {
+    let from = version_of(settings);
+    if from >= CURRENT_VERSION {
+        return false;
+    }
+
+    for migration in &MIGRATIONS[from as usize..] {
+        migration(settings);
+    }
+    settings.insert("version".to_string(), CURRENT_VERSION.into());
+    log::info!(
+        "migrated settings from version {} to {}",
+        from,
+        CURRENT_VERSION
+    );
+    true
+}
+
+/// Parse a settings file, returns the settings and whether a migration ran
+fn parse(content: &str) -> Result<(AppSettings, bool), String> {
+    let mut map = match serde_json::from_str::<Value>(content).map_err(|e| e.to_string())? {
+        Value::Object(map) => map,
+        _ => return Err("not a JSON object".to_string()),
+    };
+
+    let migrated = migrate(&mut map);
+    let settings = serde_json::from_value(Value::Object(map)).map_err(|e| e.to_string())?;
+    Ok((settings, migrated))
+}
+
+/// Load settings, upgrading old files in place
+///
+/// A file that can't be read as settings is moved aside and defaults are
+/// returned, so one bad edit doesn't break the settings view and the next
+/// save can't overwrite the original.
+pub fn load(path: &Path) -> AppSettings {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return AppSettings::default(),
+        Err(e) => return quarantine(path, &e.to_string()),
+    };
+
+    let (settings, migrated) = match parse(&content) {
+        Ok(parsed) => parsed,
+        Err(e) => return quarantine(path, &e),
+    };
+
+    // Persist the upgrade; the pre-migration file stays as the `.bak`
+    if migrated {
+        if let Err(e) = save(path, &settings) {
+            log::error!("failed to write migrated settings: {}", e);
+        }
+    }
+
+    settings
+}
+
+pub fn save(path: &Path, settings: &AppSettings) -> Result<(), String> {
+    let mut settings = settings.clone();
+    settings.version = settings.version.max(CURRENT_VERSION);
+    let content = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    crate::storage::write_atomic(path, content.as_bytes()).map_err(|e| e.to_string())
+}
+
+/// `settings.json` -> `settings.json.corrupt-<unix ms>`
+fn quarantine_path(path: &Path) -> PathBuf {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut target = path.with_file_name(format!("{}.corrupt-{}", name, millis));
+    // Never overwrite an earlier quarantined file
+    let mut n = 1;
+    while target.exists() {
+        target = path.with_file_name(format!("{}.corrupt-{}-{}", name, millis, n));
+        n += 1;
+    }
+    target
+}
+
+/// Move an unreadable settings file aside and fall back to defaults
+fn quarantine(path: &Path, reason: &str) -> AppSettings {
+    let target = quarantine_path(path);
+    match std::fs::rename(path, &target) {
+        Ok(()) => log::warn!(
+            "{} is invalid ({}), moved to {}",
+            path.display(),
+            reason,
+            target.display()
+        ),
+        Err(e) => log::error!(
+            "{} is invalid ({}) and could not be moved aside: {}",
+            path.display(),
+            reason,
+            e
+        ),
+    }
+    AppSettings::default()
+}
+
+// --------------------------------------------
+// Store
+// --------------------------------------------
+
+pub fn path(app_handle: &AppHandle) -> PathBuf {
+    app_handle
+        .path()
+        .app_config_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join("settings.json")
+}
+
+/// One changed key in a `settings-changed` event
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct SettingsChange {
+    pub key: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Payload of the `settings-changed` event
+#[derive(serde::Serialize, Clone)]
+pub struct SettingsChanged {
+    pub changes: Vec<SettingsChange>,
+    pub settings: AppSettings,
+}
+
+/// The settings loaded at startup, kept in Tauri state
+///
+/// The lock is held across the disk write, so concurrent saves can't
+/// interleave and the file always matches memory.
+pub struct SettingsStore {
+    path: PathBuf,
+    current: Mutex<AppSettings>,
+}
+
+impl SettingsStore {
+    pub fn load(path: PathBuf) -> Self {
+        let current = Mutex::new(load(&path));
+        Self { path, current }
+    }
+
+    pub fn get(&self) -> AppSettings {
+        self.current
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replace the settings, write them and apply what changed
+    pub fn set(&self, app_handle: &AppHandle, settings: AppSettings) -> Result<(), String> {
+        let mut current = self.current.lock().map_err(|e| e.to_string())?;
+
+        let mut next = settings;
+        next.version = next.version.max(CURRENT_VERSION);
+
+        let changes = diff(&current, &next);
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        save(&self.path, &next)?;
+        let previous = std::mem::replace(&mut *current, next.clone());
+        drop(current);
+
+        publish(app_handle, &previous, next, changes);
+        Ok(())
+    }
+
+    /// Re-read the file after it was changed outside the store
+    ///
+    /// Unlike startup, an invalid file is an error and is left in place, since
+    /// it is most likely a hand edit in progress. A missing file keeps the
+    /// current settings: editors that save by delete-and-recreate pass through
+    /// that state, and the write that follows triggers the real reload.
+    pub fn reload(&self, app_handle: &AppHandle) -> Result<(), String> {
+        let mut current = self.current.lock().map_err(|e| e.to_string())?;
+
+        let next = match std::fs::read_to_string(&self.path) {
+            Ok(content) => {
+                let (next, migrated) = parse(&content)?;
+                if migrated {
+                    save(&self.path, &next)?;
+                }
+                next
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        let changes = diff(&current, &next);
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let previous = std::mem::replace(&mut *current, next.clone());
+        drop(current);
+
+        publish(app_handle, &previous, next, changes);
+        Ok(())
+    }
+}
+
+fn publish(
+    app_handle: &AppHandle,
+    previous: &AppSettings,
+    settings: AppSettings,
+    changes: Vec<SettingsChange>,
+) {
+    apply(app_handle, previous, &settings);
+    let _ = app_handle.emit("settings-changed", SettingsChanged { changes, settings });
+}
+
+/// Top-level keys whose values differ
+fn diff(old: &AppSettings, new: &AppSettings) -> Vec<SettingsChange> {
+    let as_map = |settings: &AppSettings| match serde_json::to_value(settings) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    let (old, new) = (as_map(old), as_map(new));
+
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter(|key| old.get(*key) != new.get(*key))
+        .map(|key| SettingsChange {
+            key: key.clone(),
+            old: old.get(key).cloned().unwrap_or(Value::Null),
+            new: new.get(key).cloned().unwrap_or(Value::Null),
+        })
+        .collect()
+}
+
+/// Push changed settings to the Rust subsystems that use them
+///
+/// Display settings like `compact_mode` have no Rust side: the webview
+/// re-renders from the `settings-changed` event that `publish` sends next.
+fn apply(app_handle: &AppHandle, old: &AppSettings, new: &AppSettings) {
+    if old.autostart != new.autostart {
+        use tauri_plugin_autostart::ManagerExt as AutostartManagerExt;
+        let autostart = app_handle.autolaunch();
+        let result = if new.autostart {
+            autostart.enable()
+        } else {
+            autostart.disable()
+        };
+        if let Err(e) = result {
+            log::warn!("failed to update autostart: {}", e);
+        }
+    }
+
+    if (old.discovery_port_start, old.discovery_port_count)
+        != (new.discovery_port_start, new.discovery_port_count)
+    {
+        crate::discovery::set_port_range(new.discovery_port_start, new.discovery_port_count);
+    }
+
+    if old.permission_rules != new.permission_rules {
+        crate::policy::load(&new.permission_rules);
+    }
+
+    if old.shortcuts != new.shortcuts {
+        crate::shortcuts::apply(app_handle, &new.shortcuts);
+    }
+
+    if old.animate_tray_icon != new.animate_tray_icon {
+        crate::tray_icon::set_animated(app_handle, new.animate_tray_icon);
+    }
+}
+
+/// Shorthand for reading the managed store
+pub fn get(app_handle: &AppHandle) -> AppSettings {
+    app_handle.state::<SettingsStore>().get()
+}
+
+// --------------------------------------------
+// Watcher
+// --------------------------------------------
+
+// Editors often save with several writes or a rename in quick succession
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Apply hand edits to `settings.json` while the app runs
+pub fn watch(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || {
+        let path = path(&app_handle);
+        let Some(dir) = path.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        // The directory must exist to be watched
+        let _ = std::fs::create_dir_all(&dir);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut debouncer = match new_debouncer(WATCH_DEBOUNCE, tx) {
+            Ok(debouncer) => debouncer,
+            Err(e) => {
+                log::warn!("failed to create settings watcher: {}", e);
+                return;
+            }
+        };
+        // Watch the directory, not the file, so atomic saves (rename over) are seen
+        if let Err(e) = debouncer.watcher().watch(&dir, RecursiveMode::NonRecursive) {
+            log::warn!("failed to watch {}: {}", dir.display(), e);
+            return;
+        }
+
+        for result in rx {
+            let Ok(events) = result else {
+                continue;
+            };
+            if !events
+                .iter()
+                .any(|event| event.path.file_name() == path.file_name())
+            {
+                continue;
+            }
+
+            // Our own saves land here too; they reload without a diff
+            if let Err(e) = app_handle.state::<SettingsStore>().reload(&app_handle) {
+                log::warn!("ignoring invalid settings.json: {}", e);
+                let _ = app_handle
+                    .notification()
+                    .builder()
+                    .title("OpenTray settings not applied")
+                    .body(format!("settings.json is invalid: {}", e))
+                    .show();
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn migrated(value: Value) -> Value {
+        let Value::Object(mut map) = value else {
+            panic!("not an object");
+        };
+        migrate(&mut map);
+        Value::Object(map)
+    }
+
+    #[test]
+    fn migrates_v0_through_v2() {
+        let settings = migrated(json!({
+            "global_shortcut": "Alt+Space",
+            "autostart": null,
+            "custom": 1,
+        }));
+        assert_eq!(
+            settings,
+            json!({
+                "version": 2,
+                "shortcuts": { "toggle_panel": "Alt+Space" },
+                "custom": 1,
+            })
+        );
+    }
+
+    #[test]
+    fn drops_empty_v0_shortcuts() {
+        assert_eq!(
+            migrated(json!({ "global_shortcut": " " })),
+            json!({ "version": 2 })
+        );
+    }
+
+    #[test]
+    fn moves_the_v1_shortcut_into_the_action_map() {
+        let settings = migrated(json!({
+            "version": 1,
+            "global_shortcut": "Ctrl+K",
+            "shortcuts": { "new_session": "Ctrl+N" },
+        }));
+        assert_eq!(
+            settings,
+            json!({
+                "version": 2,
+                "shortcuts": { "new_session": "Ctrl+N", "toggle_panel": "Ctrl+K" },
+            })
+        );
+
+        // An existing binding wins over the old key
+        let settings = migrated(json!({
+            "version": 1,
+            "global_shortcut": "Ctrl+K",
+            "shortcuts": { "toggle_panel": "Ctrl+J" },
+        }));
+        assert_eq!(settings["shortcuts"], json!({ "toggle_panel": "Ctrl+J" }));
+    }
+
+    #[test]
+    fn leaves_current_and_newer_files_alone() {
+        for version in [CURRENT_VERSION, CURRENT_VERSION + 1] {
+            let mut map = json!({ "version": version, "global_shortcut": "Ctrl+K" })
+                .as_object()
+                .cloned()
+                .unwrap();
+            assert!(!migrate(&mut map));
+            assert!(map.contains_key("global_shortcut"));
+        }
+    }
+
+    #[test]
+    fn load_persists_the_upgrade_and_keeps_the_original() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("settings.json");
+        let original = r#"{ "global_shortcut": "Alt+Space", "custom": true }"#;
+        std::fs::write(&path, original).unwrap();
+
+        let settings = load(&path);
+        assert_eq!(settings.version, CURRENT_VERSION);
+        assert_eq!(settings.shortcuts["toggle_panel"], "Alt+Space");
+        assert_eq!(settings.extra["custom"], json!(true));
+
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], json!(CURRENT_VERSION));
+        assert!(saved.get("global_shortcut").is_none());
+        assert_eq!(
+            std::fs::read_to_string(crate::storage::backup_path(&path)).unwrap(),
+            original
+        );
+    }
+
+    #[test]
+    fn quarantines_unreadable_files() {
+        for contents in [
+            "{ not json",
+            "[]",
+            r#"{ "version": 2, "autostart": "yes" }"#,
+        ] {
+            let temp = tempfile::tempdir().unwrap();
+            let path = temp.path().join("settings.json");
+            std::fs::write(&path, contents).unwrap();
+
+            let settings = load(&path);
+            assert!(!settings.autostart);
+            assert!(!path.exists());
+
+            let moved: Vec<_> = std::fs::read_dir(temp.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+                .collect();
+            assert_eq!(moved.len(), 1);
+            assert!(moved[0].starts_with("settings.json.corrupt-"));
+            assert_eq!(
+                std::fs::read_to_string(temp.path().join(&moved[0])).unwrap(),
+                contents
+            );
+        }
+    }
+
+    #[test]
+    fn quarantines_files_that_fail_to_read() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("settings.json");
+        std::fs::write(&path, b"{ \"custom\": \"\xff\" }").unwrap();
+        assert!(!load(&path).autostart);
+        assert!(!path.exists());
+
+        // A directory in its place is moved aside too, not replaced by a save
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("settings.json");
+        std::fs::create_dir(&path).unwrap();
+        load(&path);
+        assert!(!path.exists());
+        let moved: Vec<_> = std::fs::read_dir(temp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(moved.len(), 1);
+        assert!(moved[0].is_dir());
+    }
+
+    #[test]
+    fn hand_edits_reach_the_webview_as_changes() {
+        let current = AppSettings::default();
+        let mut edited = serde_json::to_value(&current).unwrap();
+        edited["compact_mode"] = json!(true);
+
+        let (next, migrated) = parse(&edited.to_string()).unwrap();
+        assert!(!migrated);
+        let changes = diff(&current, &next);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].key, "compact_mode");
+        assert_eq!(changes[0].old, json!(false));
+        assert_eq!(changes[0].new, json!(true));
+    }
+
+    #[test]
+    fn missing_files_load_defaults_without_writing() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("settings.json");
+        assert_eq!(load(&path).version, CURRENT_VERSION);
+        assert!(!path.exists());
+    }
+}
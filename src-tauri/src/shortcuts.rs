@@ -0,0 +1,363 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// Something a global shortcut can do, keyed by its snake_case name in settings
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    TogglePanel,
+    NewSession,
+    /// Abort the session open in the panel
+    AbortSession,
+    /// Allow the oldest pending permission request once
+    ApprovePermission,
+    /// Reject the oldest pending permission request
+    DenyPermission,
+    OpenSettings,
+}
+
+impl ShortcutAction {
+    fn from_name(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
+/// Why a binding isn't active, serialized as `{ kind, message }`
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum BindingError {
+    UnknownAction(String),
+    InvalidShortcut(String),
+    /// Another action uses the same accelerator
+    Conflict(String),
+    /// The OS refused it, usually because another app owns the accelerator
+    Registration(String),
+}
+
+/// Outcome of registering one binding
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BindingStatus {
+    pub action: String,
+    pub shortcut: String,
+    pub registered: bool,
+    pub error: Option<BindingError>,
+}
+
+// Accelerators currently registered with the OS, by action
+static REGISTERED: Mutex<Vec<(ShortcutAction, Shortcut)>> = Mutex::new(Vec::new());
+
+// Result of the last `apply`, for the settings view
+static STATUS: Mutex<Vec<BindingStatus>> = Mutex::new(Vec::new());
+
+/// Default bindings: only the panel toggle is bound out of the box
+pub fn default_bindings() -> BTreeMap<String, String> {
+    BTreeMap::from([(
+        "toggle_panel".to_string(),
+        crate::command::default_shortcut(),
+    )])
+}
+
+/// Register the saved bindings
+pub fn init(app_handle: &AppHandle) {
+    apply(app_handle, &crate::settings::get(app_handle).shortcuts);
+}
+
+/// What `apply` has to do to bring the registrations in line with the bindings
+#[derive(Debug)]
+struct Plan {
+    statuses: Vec<BindingStatus>,
+    /// Registered shortcuts that are no longer wanted
+    unregister: Vec<(ShortcutAction, Shortcut)>,
+    /// Shortcuts to register, with the index of their status
+    register: Vec<(ShortcutAction, Shortcut, usize)>,
+}
+
+/// Check `bindings` and diff them against what is `registered`
+///
+/// Bindings that are invalid or share an accelerator get an error status and
+/// are left out; the others are kept when already registered with the same
+/// accelerator, and registered otherwise.
+fn plan(bindings: &BTreeMap<String, String>, registered: &[(ShortcutAction, Shortcut)]) -> Plan {
+    let mut statuses = Vec::new();
+    let mut wanted: Vec<(ShortcutAction, Shortcut, usize)> = Vec::new();
+
+    for (name, accelerator) in bindings {
+        // An empty accelerator leaves the action unbound
+        if accelerator.trim().is_empty() {
+            continue;
+        }
+
+        let mut status = BindingStatus {
+            action: name.clone(),
+            shortcut: accelerator.clone(),
+            registered: false,
+            error: None,
+        };
+
+        match ShortcutAction::from_name(name) {
+            None => status.error = Some(BindingError::UnknownAction(name.clone())),
+            Some(action) => match crate::accelerator::parse(accelerator) {
+                Err(e) => status.error = Some(BindingError::InvalidShortcut(e.to_string())),
+                Ok(shortcut) => wanted.push((action, shortcut, statuses.len())),
+            },
+        }
+        statuses.push(status);
+    }
+
+    // An accelerator bound to several actions is left out for all of them
+    let mut owners: HashMap<Shortcut, Vec<usize>> = HashMap::new();
+    for (_, shortcut, index) in &wanted {
+        owners.entry(*shortcut).or_default().push(*index);
+    }
+    wanted.retain(|(_, shortcut, index)| {
+        let shared = &owners[shortcut];
+        if shared.len() < 2 {
+            return true;
+        }
+        let others: Vec<String> = shared
+            .iter()
+            .filter(|i| *i != index)
+            .map(|i| statuses[*i].action.clone())
+            .collect();
+        statuses[*index].error = Some(BindingError::Conflict(format!(
+            "{} is also bound to {}",
+            statuses[*index].shortcut,
+            others.join(", ")
+        )));
+        false
+    });
+
+    let unregister = registered
+        .iter()
+        .filter(|(action, shortcut)| !wanted.iter().any(|(a, s, _)| a == action && s == shortcut))
+        .copied()
+        .collect();
+
+    let mut register = Vec::new();
+    for (action, shortcut, index) in wanted {
+        if registered.contains(&(action, shortcut)) {
+            statuses[index].registered = true;
+        } else {
+            register.push((action, shortcut, index));
+        }
+    }
+
+    Plan {
+        statuses,
+        unregister,
+        register,
+    }
+}
+
+/// Bring the registered shortcuts in line with `bindings`
+///
+/// Only bindings that changed are unregistered and registered again. Bindings
+/// that are invalid or share an accelerator are skipped and reported; the
+/// others are still applied.
+pub fn apply(app_handle: &AppHandle, bindings: &BTreeMap<String, String>) -> Vec<BindingStatus> {
+    let Ok(mut registered) = REGISTERED.lock() else {
+        return plan(bindings, &[]).statuses;
+    };
+    let Plan {
+        mut statuses,
+        unregister,
+        register,
+    } = plan(bindings, &registered);
+    let global_shortcut = app_handle.global_shortcut();
+
+    // Release what's no longer wanted first, so an accelerator can move between actions
+    registered.retain(|binding| {
+        if !unregister.contains(binding) {
+            return true;
+        }
+        let _ = global_shortcut.unregister(binding.1);
+        false
+    });
+
+    for (action, shortcut, index) in register {
+        let handle = app_handle.clone();
+        match global_shortcut.on_shortcut(shortcut, move |_app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                run(&handle, action);
+            }
+        }) {
+            Ok(()) => {
+                registered.push((action, shortcut));
+                statuses[index].registered = true;
+            }
+            Err(e) => {
+                statuses[index].error = Some(BindingError::Registration(e.to_string()));
+            }
+        }
+    }
+    drop(registered);
+
+    for status in statuses.iter().filter(|s| s.error.is_some()) {
+        log::warn!(
+            "{} ({}) not registered: {:?}",
+            status.action,
+            status.shortcut,
+            status.error
+        );
+    }
+
+    if let Ok(mut last) = STATUS.lock() {
+        *last = statuses.clone();
+    }
+    let _ = app_handle.emit("shortcuts-changed", &statuses);
+    statuses
+}
+
+fn run(app_handle: &AppHandle, action: ShortcutAction) {
+    match action {
+        ShortcutAction::TogglePanel => crate::command::toggle_panel_internal(app_handle),
+        ShortcutAction::NewSession => {
+            let _ = app_handle.emit("tray-new-session", ());
+            crate::command::show_panel_internal(app_handle);
+        }
+        ShortcutAction::AbortSession => {
+            let _ = app_handle.emit("shortcut-abort-session", ());
+        }
+        ShortcutAction::ApprovePermission => {
+            answer_front(app_handle, opentray_client::PermissionReply::Once)
+        }
+        ShortcutAction::DenyPermission => {
+            answer_front(app_handle, opentray_client::PermissionReply::Reject)
+        }
+        ShortcutAction::OpenSettings => {
+            let _ = app_handle.emit("tray-settings", ());
+            crate::command::show_panel_internal(app_handle);
+        }
+    }
+}
+
+/// Answer the request shown in the permission popup
+fn answer_front(app_handle: &AppHandle, reply: opentray_client::PermissionReply) {
+    let Some(pending) = crate::permissions::front() else {
+        return;
+    };
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = crate::command::answer_permission(
+            &app_handle,
+            pending.instance_url.clone(),
+            &pending.id,
+            reply,
+            false,
+        )
+        .await
+        {
+            log::warn!("failed to answer {}: {}", pending.id, e);
+        }
+    });
+}
+
+/// Registration result for each binding from the last time shortcuts were applied
+#[tauri::command]
+pub fn get_shortcut_status() -> Result<Vec<BindingStatus>, String> {
+    Ok(STATUS.lock().map(|s| s.clone()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(action, shortcut)| (action.to_string(), shortcut.to_string()))
+            .collect()
+    }
+
+    fn shortcut(accelerator: &str) -> Shortcut {
+        crate::accelerator::parse(accelerator).unwrap()
+    }
+
+    fn error_kind(status: &BindingStatus) -> Option<&'static str> {
+        status.error.as_ref().map(|e| match e {
+            BindingError::UnknownAction(_) => "unknown",
+            BindingError::InvalidShortcut(_) => "invalid",
+            BindingError::Conflict(_) => "conflict",
+            BindingError::Registration(_) => "registration",
+        })
+    }
+
+    #[test]
+    fn plans_a_fresh_registration() {
+        let plan = plan(
+            &bindings(&[("toggle_panel", "Alt+Space"), ("new_session", "")]),
+            &[],
+        );
+        // Empty accelerators are skipped without a status
+        assert_eq!(plan.statuses.len(), 1);
+        assert!(plan.unregister.is_empty());
+        assert_eq!(
+            plan.register,
+            vec![(ShortcutAction::TogglePanel, shortcut("Alt+Space"), 0)]
+        );
+    }
+
+    /// Error kinds in status order, checking that the valid binding is still registered
+    fn error_kinds(pairs: &[(&str, &str)]) -> Vec<Option<&'static str>> {
+        let plan = plan(&bindings(pairs), &[]);
+        assert_eq!(plan.register.len(), 1, "{:?}", pairs);
+        assert!(plan.statuses.iter().all(|s| !s.registered));
+        plan.statuses.iter().map(error_kind).collect()
+    }
+
+    #[test]
+    fn reports_bad_bindings_and_applies_the_rest() {
+        // Statuses come in action name order
+        assert_eq!(
+            error_kinds(&[
+                ("toggle_panel", "Ctrl+K"),
+                ("new_session", "Ctrl+K"),
+                ("open_settings", "Ctrl+,"),
+            ]),
+            vec![Some("conflict"), None, Some("conflict")]
+        );
+        assert_eq!(
+            error_kinds(&[("fly_away", "Ctrl+F"), ("toggle_panel", "Alt+Space")]),
+            vec![Some("unknown"), None]
+        );
+        assert_eq!(
+            error_kinds(&[("new_session", "Ctrl+Nope"), ("toggle_panel", "Alt+Space")]),
+            vec![Some("invalid"), None]
+        );
+    }
+
+    #[test]
+    fn keeps_unchanged_registrations() {
+        let registered = [(ShortcutAction::TogglePanel, shortcut("Alt+Space"))];
+        let plan = plan(&bindings(&[("toggle_panel", "Alt+Space")]), &registered);
+        assert!(plan.unregister.is_empty());
+        assert!(plan.register.is_empty());
+        assert!(plan.statuses[0].registered);
+    }
+
+    #[test]
+    fn moves_an_accelerator_between_actions() {
+        let registered = [
+            (ShortcutAction::TogglePanel, shortcut("Ctrl+K")),
+            (ShortcutAction::OpenSettings, shortcut("Ctrl+,")),
+        ];
+        let plan = plan(
+            &bindings(&[("new_session", "Ctrl+K"), ("open_settings", "Ctrl+,")]),
+            &registered,
+        );
+        assert_eq!(
+            plan.unregister,
+            vec![(ShortcutAction::TogglePanel, shortcut("Ctrl+K"))]
+        );
+        assert_eq!(
+            plan.register,
+            vec![(ShortcutAction::NewSession, shortcut("Ctrl+K"), 0)]
+        );
+        assert!(plan.statuses[1].registered);
+    }
+}
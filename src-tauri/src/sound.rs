@@ -0,0 +1,375 @@
+use std::fmt;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use rodio::{Decoder, OutputStream, OutputStreamBuilder, Sink};
+use tauri::AppHandle;
+
+// How long `preview_sound` waits for the audio thread to start playing
+const PREVIEW_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Formats the decoder is built with
+const EXTENSIONS: &[&str] = &["wav", "ogg"];
+
+// The output stream isn't `Send` everywhere, so one thread owns it and plays what it's sent
+static PLAYER: LazyLock<Mutex<Sender<Playback>>> = LazyLock::new(|| {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || run(receiver));
+    Mutex::new(sender)
+});
+
+/// Which sound an event makes
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SoundCategory {
+    Done,
+    Error,
+    Permission,
+}
+
+/// Sound for one category
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+pub struct CategorySound {
+    /// WAV or OGG file to play instead of the bundled sound
+    #[serde(default)]
+    pub file: Option<String>,
+    /// 0.0 (silent) to 1.0
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+}
+
+impl Default for CategorySound {
+    fn default() -> Self {
+        Self {
+            file: None,
+            volume: default_volume(),
+        }
+    }
+}
+
+fn default_volume() -> f32 {
+    0.8
+}
+
+/// `AppSettings.sounds`, played when `sound_enabled` is on
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, PartialEq, Debug)]
+pub struct SoundSettings {
+    #[serde(default)]
+    pub done: CategorySound,
+    #[serde(default)]
+    pub error: CategorySound,
+    #[serde(default)]
+    pub permission: CategorySound,
+}
+
+impl SoundSettings {
+    pub fn get(&self, category: SoundCategory) -> &CategorySound {
+        match category {
+            SoundCategory::Done => &self.done,
+            SoundCategory::Error => &self.error,
+            SoundCategory::Permission => &self.permission,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Debug)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum SoundError {
+    /// No output device, or it couldn't be opened
+    NoDevice(String),
+    Unreadable(String),
+    /// Not a WAV or OGG file we can decode
+    Unsupported(String),
+}
+
+impl fmt::Display for SoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SoundError::NoDevice(message) => write!(f, "No audio output: {}", message),
+            SoundError::Unreadable(message) => write!(f, "Can't read sound file: {}", message),
+            SoundError::Unsupported(message) => write!(f, "Unsupported sound: {}", message),
+        }
+    }
+}
+
+struct Playback {
+    sound: Decoder<Cursor<Vec<u8>>>,
+    volume: f32,
+    /// Told whether the sound started, for previews
+    started: Option<Sender<Result<(), SoundError>>>,
+}
+
+fn bundled(category: SoundCategory) -> &'static [u8] {
+    match category {
+        SoundCategory::Done => include_bytes!("../sounds/done.wav"),
+        SoundCategory::Error => include_bytes!("../sounds/error.wav"),
+        SoundCategory::Permission => include_bytes!("../sounds/permission.wav"),
+    }
+}
+
+/// A custom sound file, which must be a WAV or OGG file in `roots`
+fn custom_file(file: &str, roots: &[PathBuf]) -> Result<PathBuf, SoundError> {
+    let path = crate::sandbox::resolve_within(file, roots)
+        .map_err(|e| SoundError::Unreadable(e.to_string()))?;
+    if !path.is_file() {
+        return Err(SoundError::Unreadable(format!("{} is not a file", file)));
+    }
+    let supported = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    if !supported {
+        return Err(SoundError::Unsupported(format!(
+            "{} is not a WAV or OGG file",
+            file
+        )));
+    }
+    Ok(path)
+}
+
+/// Read and decode the sound for `category`, the bundled one when no file is set
+///
+/// `roots` are the directories a custom file may come from, `sandbox::sound_roots`.
+fn decode(
+    roots: &[PathBuf],
+    category: SoundCategory,
+    file: Option<&str>,
+) -> Result<Decoder<Cursor<Vec<u8>>>, SoundError> {
+    let bytes = match file.filter(|f| !f.trim().is_empty()) {
+        Some(file) => {
+            let path = custom_file(file, roots)?;
+            std::fs::read(&path).map_err(|e| SoundError::Unreadable(format!("{}: {}", file, e)))?
+        }
+        None => bundled(category).to_vec(),
+    };
+    Decoder::new(Cursor::new(bytes)).map_err(|e| SoundError::Unsupported(e.to_string()))
+}
+
+/// Like `decode`, falling back to the bundled sound if the chosen file went missing
+fn decode_or_bundled(
+    roots: &[PathBuf],
+    category: SoundCategory,
+    file: Option<&str>,
+) -> Result<Decoder<Cursor<Vec<u8>>>, SoundError> {
+    decode(roots, category, file).or_else(|e| {
+        log::warn!("{}, using the default {:?} sound", e, category);
+        decode(roots, category, None)
+    })
+}
+
+/// Play on the audio thread; `started` hears back once it's playing or has failed
+fn send(playback: Playback) -> Result<(), SoundError> {
+    PLAYER
+        .lock()
+        .map_err(|e| SoundError::NoDevice(e.to_string()))?
+        .send(playback)
+        .map_err(|_| SoundError::NoDevice("audio thread stopped".to_string()))
+}
+
+/// The audio thread: opens the output on first use and again after it failed,
+/// so a device plugged in later is picked up
+fn run(receiver: Receiver<Playback>) {
+    let mut stream: Option<OutputStream> = None;
+    let mut warned = false;
+
+    for playback in receiver {
+        if stream.is_none() {
+            match OutputStreamBuilder::open_default_stream() {
+                Ok(mut opened) => {
+                    opened.log_on_drop(false);
+                    stream = Some(opened);
+                    warned = false;
+                }
+                Err(e) => {
+                    if !warned {
+                        log::warn!("no audio output, sounds are off: {}", e);
+                        warned = true;
+                    }
+                    if let Some(started) = playback.started {
+                        let _ = started.send(Err(SoundError::NoDevice(e.to_string())));
+                    }
+                    continue;
+                }
+            }
+        }
+        let Some(stream) = &stream else {
+            continue;
+        };
+
+        let sink = Sink::connect_new(stream.mixer());
+        sink.set_volume(playback.volume.clamp(0.0, 1.0));
+        sink.append(playback.sound);
+        sink.detach();
+        if let Some(started) = playback.started {
+            let _ = started.send(Ok(()));
+        }
+    }
+}
+
+/// Play the configured sound for `category` if `sound_enabled` is on
+///
+/// Failures are logged, never surfaced: a missing device or a broken file
+/// shouldn't get in the way of the notification it accompanies.
+pub fn play(app_handle: &AppHandle, category: SoundCategory) {
+    let settings = crate::settings::get(app_handle);
+    if !settings.sound_enabled {
+        return;
+    }
+    let sound = settings.sounds.get(category);
+    if sound.volume <= 0.0 {
+        return;
+    }
+
+    let roots = crate::sandbox::sound_roots(app_handle);
+    let decoded = decode_or_bundled(&roots, category, sound.file.as_deref());
+    let result = decoded.and_then(|decoded| {
+        send(Playback {
+            sound: decoded,
+            volume: sound.volume,
+            started: None,
+        })
+    });
+    if let Err(e) = result {
+        log::warn!("failed to play {:?} sound: {}", category, e);
+    }
+}
+
+// --------------------------------------------
+// Commands
+// --------------------------------------------
+
+/// Play a category's sound as the settings view would have it, even with sounds off
+///
+/// `file` and `volume` default to the saved settings, so unsaved edits can be tried.
+/// Reading the file and waiting for playback happen off the main thread.
+#[tauri::command]
+pub async fn preview_sound(
+    app_handle: AppHandle,
+    category: SoundCategory,
+    file: Option<String>,
+    volume: Option<f32>,
+) -> Result<(), SoundError> {
+    let saved = crate::settings::get(&app_handle)
+        .sounds
+        .get(category)
+        .clone();
+    let file = file.or(saved.file);
+    let volume = volume.unwrap_or(saved.volume);
+    let roots = crate::sandbox::sound_roots(&app_handle);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let (started, result) = mpsc::channel();
+        send(Playback {
+            sound: decode(&roots, category, file.as_deref())?,
+            volume,
+            started: Some(started),
+        })?;
+        result
+            .recv_timeout(PREVIEW_TIMEOUT)
+            .unwrap_or_else(|_| Err(SoundError::NoDevice("audio output timed out".to_string())))
+    })
+    .await
+    .map_err(|e| SoundError::NoDevice(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    const CATEGORIES: [SoundCategory; 3] = [
+        SoundCategory::Done,
+        SoundCategory::Error,
+        SoundCategory::Permission,
+    ];
+
+    /// A sound root holding a valid WAV and a few files that aren't
+    fn root() -> (tempfile::TempDir, PathBuf) {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        std::fs::write(root.join("ding.WAV"), bundled(SoundCategory::Done)).unwrap();
+        std::fs::write(root.join("broken.ogg"), b"not a sound").unwrap();
+        std::fs::write(root.join("notes.txt"), b"").unwrap();
+        std::fs::create_dir(root.join("folder.wav")).unwrap();
+        (temp, root)
+    }
+
+    fn file(root: &Path, name: &str) -> String {
+        root.join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn bundled_sounds_decode() {
+        for category in CATEGORIES {
+            assert!(decode(&[], category, None).is_ok(), "{:?}", category);
+            assert!(decode(&[], category, Some(" ")).is_ok(), "{:?}", category);
+        }
+    }
+
+    #[test]
+    fn custom_files_must_be_wav_or_ogg_files_in_a_root() {
+        let (_temp, root) = root();
+        let roots = [root.clone()];
+        assert_eq!(
+            custom_file(&file(&root, "ding.WAV"), &roots).unwrap(),
+            root.join("ding.WAV")
+        );
+        assert!(matches!(
+            custom_file(&file(&root, "notes.txt"), &roots),
+            Err(SoundError::Unsupported(_))
+        ));
+        assert!(matches!(
+            custom_file(&file(&root, "folder.wav"), &roots),
+            Err(SoundError::Unreadable(_))
+        ));
+        assert!(matches!(
+            custom_file(&file(&root, "missing.wav"), &roots),
+            Err(SoundError::Unreadable(_))
+        ));
+        // Outside the roots, even though the file is fine
+        assert!(matches!(
+            custom_file(&file(&root, "ding.WAV"), &[root.join("elsewhere")]),
+            Err(SoundError::Unreadable(_))
+        ));
+        assert!(matches!(
+            custom_file(&file(&root, "../ding.WAV"), &roots),
+            Err(SoundError::Unreadable(_))
+        ));
+    }
+
+    #[test]
+    fn falls_back_to_the_bundled_sound() {
+        let (_temp, root) = root();
+        let roots = [root.clone()];
+        assert!(decode(&roots, SoundCategory::Done, Some(&file(&root, "ding.WAV"))).is_ok());
+
+        let broken = file(&root, "broken.ogg");
+        assert!(matches!(
+            decode(&roots, SoundCategory::Done, Some(&broken)),
+            Err(SoundError::Unsupported(_))
+        ));
+        assert!(decode_or_bundled(&roots, SoundCategory::Done, Some(&broken)).is_ok());
+        let missing = file(&root, "missing.wav");
+        assert!(decode_or_bundled(&roots, SoundCategory::Error, Some(&missing)).is_ok());
+    }
+
+    #[test]
+    fn fills_in_missing_settings() {
+        let settings: SoundSettings =
+            serde_json::from_value(serde_json::json!({ "done": { "file": "/a.wav" } })).unwrap();
+        assert_eq!(settings.done.file.as_deref(), Some("/a.wav"));
+        assert_eq!(settings.done.volume, default_volume());
+        assert_eq!(settings.error, CategorySound::default());
+        assert_eq!(
+            settings.get(SoundCategory::Permission),
+            &settings.permission
+        );
+
+        let empty: SoundSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(empty, SoundSettings::default());
+        assert_eq!(empty.done.volume, 0.8);
+    }
+}
@@ -0,0 +1,255 @@
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Tells apart the temp files of concurrent writes from this process
+static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+/// `settings.json` -> `settings.json.bak`
+pub fn backup_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name: OsString = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// A unique temp file next to `path`
+fn tmp_path(path: &Path) -> PathBuf {
+    with_suffix(
+        path,
+        &format!(
+            ".tmp-{}-{}",
+            std::process::id(),
+            NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+        ),
+    )
+}
+
+/// The file a symlink points at, so writes replace it rather than the link
+fn resolve(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Write a file so readers only ever see the old or the new content
+///
+/// The data goes to a temp file in the same directory, is fsynced and then
+/// renamed over the target. The previous version is kept as `<name>.bak`.
+/// A symlinked target stays a symlink; the file it points at is replaced.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let path = &resolve(path);
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+
+    let tmp = tmp_path(path);
+    let result = write_and_swap(path, &tmp, contents);
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result?;
+
+    sync_dir(dir);
+    Ok(())
+}
+
+fn write_and_swap(path: &Path, tmp: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    if let Ok(metadata) = fs::metadata(path) {
+        // Keep the original permissions, e.g. a config readable only by the user
+        fs::set_permissions(tmp, metadata.permissions())?;
+
+        back_up(path)?;
+    }
+
+    fs::rename(tmp, path)
+}
+
+/// Make the current file the backup without ever leaving a partial `.bak`
+///
+/// The file is hard-linked (or, where links aren't supported, copied and
+/// fsynced) to a temp name that is then renamed over the old backup.
+fn back_up(path: &Path) -> io::Result<()> {
+    let backup = backup_path(path);
+    let tmp = tmp_path(&backup);
+    let result = fs::hard_link(path, &tmp)
+        .or_else(|_| {
+            fs::copy(path, &tmp)?;
+            File::open(&tmp)?.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, &backup));
+    // Also when the rename succeeded: renaming onto another link to the same
+    // file is a no-op that leaves the temp name in place
+    let _ = fs::remove_file(&tmp);
+    result
+}
+
+/// Make the rename durable (no-op where directories can't be opened)
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+/// Put `<name>.bak` back in place; the current version becomes the new backup
+pub fn restore_backup(path: &Path) -> io::Result<()> {
+    let path = &resolve(path);
+    let backup = backup_path(path);
+    let contents = fs::read(&backup).map_err(|e| {
+        if e.kind() == io::ErrorKind::NotFound {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No backup found for {}", path.display()),
+            )
+        } else {
+            e
+        }
+    })?;
+    write_atomic(path, &contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn creates_new_files_without_a_backup() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("nested/settings.json");
+        write_atomic(&path, b"one").unwrap();
+        assert_eq!(read(&path), "one");
+        assert!(!backup_path(&path).exists());
+    }
+
+    #[test]
+    fn overwrites_and_keeps_the_previous_version() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("settings.json");
+        write_atomic(&path, b"one").unwrap();
+        write_atomic(&path, b"two").unwrap();
+        write_atomic(&path, b"three").unwrap();
+
+        assert_eq!(read(&path), "three");
+        assert_eq!(read(&backup_path(&path)), "two");
+        // No temp files left behind
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn moves_the_previous_file_into_the_backup() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("settings.json");
+        write_atomic(&path, b"one").unwrap();
+        let original = fs::metadata(&path).unwrap().ino();
+        write_atomic(&path, b"two").unwrap();
+
+        // The old file itself, not a copy that a crash could cut short
+        let backup = fs::metadata(backup_path(&path)).unwrap();
+        assert_eq!(backup.ino(), original);
+        assert_eq!(backup.nlink(), 1);
+        assert_eq!(fs::metadata(&path).unwrap().nlink(), 1);
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("config.json");
+        write_atomic(&path, b"one").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        write_atomic(&path, b"two").unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_through_symlinks() {
+        let temp = tempfile::tempdir().unwrap();
+        let dotfiles = temp.path().join("dotfiles");
+        let real = dotfiles.join("settings.json");
+        let link = temp.path().join("settings.json");
+        write_atomic(&real, b"one").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        write_atomic(&link, b"two").unwrap();
+        assert!(link.is_symlink());
+        assert_eq!(read(&real), "two");
+        assert_eq!(read(&backup_path(&real)), "one");
+        assert!(!backup_path(&link).exists());
+
+        restore_backup(&link).unwrap();
+        assert!(link.is_symlink());
+        assert_eq!(read(&real), "one");
+    }
+
+    #[test]
+    fn concurrent_writes_never_leave_a_partial_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("config.json");
+        let contents: Vec<Vec<u8>> = (0..8u8).map(|n| vec![b'a' + n; 64 * 1024]).collect();
+        std::thread::scope(|scope| {
+            for data in &contents {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        write_atomic(path, data).unwrap();
+                    }
+                });
+            }
+        });
+
+        let written = fs::read(&path).unwrap();
+        assert!(contents.contains(&written));
+        // Only the file and its backup, no temp files left behind
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn restores_the_backup() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("settings.json");
+        write_atomic(&path, b"good").unwrap();
+        write_atomic(&path, b"bad").unwrap();
+
+        restore_backup(&path).unwrap();
+        assert_eq!(read(&path), "good");
+        assert_eq!(read(&backup_path(&path)), "bad");
+    }
+
+    #[test]
+    fn restoring_without_a_backup_fails() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("settings.json");
+        write_atomic(&path, b"only").unwrap();
+
+        let error = restore_backup(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert_eq!(read(&path), "only");
+    }
+}
@@ -1,6 +1,5 @@
 use tauri::{
     image::Image,
-    menu::{MenuBuilder, MenuItemBuilder},
     tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent},
     AppHandle, Emitter,
 };
@@ -24,25 +23,7 @@ pub fn create(app_handle: &AppHandle) -> tauri::Result<TrayIcon> {
     #[cfg(not(target_os = "macos"))]
     let icon = Image::from_bytes(include_bytes!("../icons/tray-dark.png"))?;
 
-    // Build context menu
-    let show_item = MenuItemBuilder::with_id("show", "Show OpenTray").build(app_handle)?;
-    let new_session_item =
-        MenuItemBuilder::with_id("new_session", "New Session").build(app_handle)?;
-    let separator1 = tauri::menu::PredefinedMenuItem::separator(app_handle)?;
-    let refresh_item = MenuItemBuilder::with_id("refresh", "Refresh").build(app_handle)?;
-    let settings_item = MenuItemBuilder::with_id("settings", "Settings...").build(app_handle)?;
-    let separator2 = tauri::menu::PredefinedMenuItem::separator(app_handle)?;
-    let quit_item = MenuItemBuilder::with_id("quit", "Quit OpenTray").build(app_handle)?;
-
-    let menu = MenuBuilder::new(app_handle)
-        .item(&show_item)
-        .item(&new_session_item)
-        .item(&separator1)
-        .item(&refresh_item)
-        .item(&settings_item)
-        .item(&separator2)
-        .item(&quit_item)
-        .build()?;
+    let menu = crate::tray_menu::build(app_handle)?;
 
     #[cfg(target_os = "macos")]
     let builder = TrayIconBuilder::with_id("tray")
@@ -82,7 +63,9 @@ pub fn create(app_handle: &AppHandle) -> tauri::Result<TrayIcon> {
                 "quit" => {
                     app.exit(0);
                 }
-                _ => {}
+                id => {
+                    crate::tray_menu::handle(app, id);
+                }
             }
         })
         .on_tray_icon_event(|tray, event| {
@@ -103,6 +86,7 @@ pub fn create(app_handle: &AppHandle) -> tauri::Result<TrayIcon> {
                         return;
                     }
 
+                    crate::tray_icon::clear_errors(app_handle);
                     position_panel(app_handle, 0.0);
                     panel.show();
                 }
@@ -115,6 +99,7 @@ pub fn create(app_handle: &AppHandle) -> tauri::Result<TrayIcon> {
                             let _ = window.hide();
                         } else {
                             // Position window near system tray
+                            crate::tray_icon::clear_errors(app_handle);
                             position_window_near_tray(&window);
                             let _ = window.show();
                             let _ = window.set_focus();
@@ -154,25 +139,3 @@ pub fn position_window_near_tray(window: &tauri::WebviewWindow) {
         }
     }
 }
-
-/// Update tray icon based on system theme (Windows/Linux)
-#[cfg(not(target_os = "macos"))]
-pub fn update_icon_for_theme(app_handle: &AppHandle, theme: &str) -> tauri::Result<()> {
-    // tray-dark.png = dark icon for light backgrounds
-    // tray-light.png = light icon for dark backgrounds
-    let icon_bytes = if theme == "dark" {
-        // Dark system theme = dark taskbar = needs light icon
-        include_bytes!("../icons/tray-light.png").as_slice()
-    } else {
-        // Light system theme = light taskbar = needs dark icon
-        include_bytes!("../icons/tray-dark.png").as_slice()
-    };
-
-    let icon = Image::from_bytes(icon_bytes)?;
-
-    if let Some(tray) = app_handle.tray_by_id("tray") {
-        tray.set_icon(Some(icon))?;
-    }
-
-    Ok(())
-}
@@ -0,0 +1,451 @@
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use image::{Rgba, RgbaImage};
+use tauri::image::Image;
+use tauri::AppHandle;
+
+use crate::events::SessionStatus;
+
+/// System theme the tray sits on, as reported by the webview
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TrayTheme {
+    Light,
+    Dark,
+}
+
+/// What the status overlay shows, most urgent last
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TrayStatus {
+    Idle,
+    Busy,
+    Error,
+    AwaitingPermission,
+}
+
+/// Everything that decides how the tray icon looks
+///
+/// Each distinct state is rendered once and cached.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TrayIconState {
+    pub theme: TrayTheme,
+    pub status: TrayStatus,
+    /// Shown count, capped at 10 ("9+")
+    pub badge: usize,
+    /// Animation frame of the busy dot, 0 when still
+    pub frame: u8,
+}
+
+/// What feeds the state, kept apart so one source can't overwrite another
+struct Inputs {
+    theme: TrayTheme,
+    /// Sessions that are working, by (instance URL, session ID)
+    busy: HashSet<(String, String)>,
+    /// Sessions whose last run failed, until they run again
+    failed: HashSet<(String, String)>,
+    pending_permissions: usize,
+    /// Notifications in the inbox not marked read
+    unread: usize,
+    /// Count sent by the webview
+    count: usize,
+    /// Pulse the busy dot (the `animate_tray_icon` setting)
+    animated: bool,
+    frame: u8,
+}
+
+static INPUTS: LazyLock<Mutex<Inputs>> = LazyLock::new(|| {
+    Mutex::new(Inputs {
+        theme: TrayTheme::Light,
+        busy: HashSet::new(),
+        failed: HashSet::new(),
+        pending_permissions: 0,
+        unread: 0,
+        count: 0,
+        animated: true,
+        frame: 0,
+    })
+});
+
+// Rendered icons by state
+static CACHE: LazyLock<Mutex<HashMap<TrayIconState, Image<'static>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// State currently on the tray, so unchanged updates are skipped
+static APPLIED: Mutex<Option<TrayIconState>> = Mutex::new(None);
+
+// Whether the animation thread is running
+static ANIMATING: AtomicBool = AtomicBool::new(false);
+
+// Whether an icon change is waiting for the main thread
+static QUEUED: AtomicBool = AtomicBool::new(false);
+
+// One pulse per second
+const FRAME_COUNT: u8 = 12;
+const FRAME_INTERVAL: Duration = Duration::from_millis(1000 / FRAME_COUNT as u64);
+
+const BUSY_COLOR: Rgba<u8> = Rgba([10, 132, 255, 255]);
+const ERROR_COLOR: Rgba<u8> = Rgba([255, 59, 48, 255]);
+const PERMISSION_COLOR: Rgba<u8> = Rgba([255, 159, 10, 255]);
+
+impl Inputs {
+    fn state(&self) -> TrayIconState {
+        let status = if self.pending_permissions > 0 {
+            TrayStatus::AwaitingPermission
+        } else if !self.failed.is_empty() {
+            TrayStatus::Error
+        } else if !self.busy.is_empty() {
+            TrayStatus::Busy
+        } else {
+            TrayStatus::Idle
+        };
+
+        // Pending permissions, then unread notifications, win over the webview's count
+        let badge = if self.pending_permissions > 0 {
+            self.pending_permissions
+        } else if self.unread > 0 {
+            self.unread
+        } else {
+            self.count
+        };
+
+        TrayIconState {
+            theme: self.theme,
+            status,
+            badge: badge.min(10),
+            frame: if self.animates(status) { self.frame } else { 0 },
+        }
+    }
+
+    fn animates(&self, status: TrayStatus) -> bool {
+        self.animated && status == TrayStatus::Busy
+    }
+}
+
+impl TrayIconState {
+    /// Plain icon with no overlays
+    fn is_plain(&self) -> bool {
+        self.status == TrayStatus::Idle && self.badge == 0
+    }
+
+    /// On macOS the plain icon is a template the menubar tints itself;
+    /// overlays need color, so those use the themed icon instead
+    fn is_template(&self) -> bool {
+        cfg!(target_os = "macos") && self.is_plain()
+    }
+}
+
+/// Compose the icon for `state`
+pub fn render(state: &TrayIconState) -> RgbaImage {
+    let base: &[u8] = if state.is_template() {
+        include_bytes!("../icons/tray-template.png")
+    } else if state.theme == TrayTheme::Dark {
+        // Dark system theme = dark taskbar = needs light icon
+        include_bytes!("../icons/tray-light.png")
+    } else {
+        include_bytes!("../icons/tray-dark.png")
+    };
+    let mut icon = image::load_from_memory(base)
+        .expect("bundled tray icon")
+        .to_rgba8();
+
+    match state.status {
+        TrayStatus::Idle => {}
+        TrayStatus::Busy => crate::badge::dot(&mut icon, pulse(BUSY_COLOR, state.frame)),
+        TrayStatus::Error => crate::badge::dot(&mut icon, ERROR_COLOR),
+        TrayStatus::AwaitingPermission => crate::badge::dot(&mut icon, PERMISSION_COLOR),
+    }
+    if let Some(text) = crate::badge::label(state.badge) {
+        crate::badge::draw(&mut icon, &text);
+    }
+
+    icon
+}
+
+/// Fade `color` in and out over the animation frames, fully opaque at frame 0
+fn pulse(color: Rgba<u8>, frame: u8) -> Rgba<u8> {
+    let phase = frame as f32 / FRAME_COUNT as f32 * 2.0 * PI;
+    let opacity = 0.65 + 0.35 * phase.cos();
+    Rgba([
+        color[0],
+        color[1],
+        color[2],
+        (color[3] as f32 * opacity).round() as u8,
+    ])
+}
+
+/// Put the icon for the current inputs on the tray, rendering it on first use
+///
+/// Only the state swap happens under `APPLIED`. The tray is changed on the main
+/// thread without waiting for it, since the main thread takes `APPLIED` too.
+fn refresh(app_handle: &AppHandle) -> tauri::Result<()> {
+    let Some(state) = INPUTS.lock().ok().map(|inputs| inputs.state()) else {
+        return Ok(());
+    };
+
+    {
+        let Ok(mut applied) = APPLIED.lock() else {
+            return Ok(());
+        };
+        if *applied == Some(state) {
+            return Ok(());
+        }
+        *applied = Some(state);
+    }
+
+    // Render off the main thread
+    icon(state);
+
+    // The queued change shows the newest state, so one is enough
+    if QUEUED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let handle = app_handle.clone();
+    let result = app_handle.run_on_main_thread(move || {
+        QUEUED.store(false, Ordering::SeqCst);
+        let Some(state) = APPLIED.lock().ok().and_then(|applied| *applied) else {
+            return;
+        };
+        if let Err(e) = set_icon(&handle, state) {
+            log::warn!("failed to update tray icon: {}", e);
+            // Let the next refresh try again
+            if let Ok(mut applied) = APPLIED.lock() {
+                *applied = None;
+            }
+        }
+    });
+    if result.is_err() {
+        QUEUED.store(false, Ordering::SeqCst);
+        if let Ok(mut applied) = APPLIED.lock() {
+            *applied = None;
+        }
+    }
+    result
+}
+
+/// The icon for `state`, from the cache or freshly rendered
+fn icon(state: TrayIconState) -> Image<'static> {
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .entry(state)
+        .or_insert_with(|| {
+            let image = render(&state);
+            let (width, height) = image.dimensions();
+            Image::new_owned(image.into_raw(), width, height)
+        })
+        .clone()
+}
+
+/// Show `state` on the tray, called on the main thread
+fn set_icon(app_handle: &AppHandle, state: TrayIconState) -> tauri::Result<()> {
+    if let Some(tray) = app_handle.tray_by_id("tray") {
+        tray.set_icon(Some(icon(state)))?;
+        #[cfg(target_os = "macos")]
+        tray.set_icon_as_template(state.is_template())?;
+    }
+    Ok(())
+}
+
+/// Change one input and refresh the icon if the state changed
+fn update(app_handle: &AppHandle, change: impl FnOnce(&mut Inputs)) {
+    let animate = match INPUTS.lock() {
+        Ok(mut inputs) => {
+            change(&mut inputs);
+            inputs.animates(inputs.state().status)
+        }
+        Err(_) => false,
+    };
+    if let Err(e) = refresh(app_handle) {
+        log::warn!("failed to update tray icon: {}", e);
+    }
+    if animate {
+        start_animation(app_handle);
+    }
+}
+
+/// Step the busy dot on a timer thread until nothing is busy or animation is turned off
+fn start_animation(app_handle: &AppHandle) {
+    if ANIMATING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(FRAME_INTERVAL);
+
+        let running = match INPUTS.lock() {
+            Ok(mut inputs) => {
+                let running = inputs.animates(inputs.state().status);
+                inputs.frame = if running {
+                    (inputs.frame + 1) % FRAME_COUNT
+                } else {
+                    0
+                };
+                running
+            }
+            Err(_) => false,
+        };
+        if !running {
+            // `update` may have seen the flag still set just before this, so check once more
+            ANIMATING.store(false, Ordering::SeqCst);
+            let restart = INPUTS
+                .lock()
+                .map(|inputs| inputs.animates(inputs.state().status))
+                .unwrap_or(false);
+            if !restart || ANIMATING.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            continue;
+        }
+
+        // Only queues the frame, a busy main thread just skips some
+        if let Err(e) = refresh(&app_handle) {
+            log::warn!("failed to animate tray icon: {}", e);
+        }
+    });
+}
+
+// --------------------------------------------
+// Inputs
+// --------------------------------------------
+
+pub fn set_theme(app_handle: &AppHandle, theme: TrayTheme) {
+    update(app_handle, |inputs| inputs.theme = theme);
+}
+
+pub fn set_pending_permissions(app_handle: &AppHandle, count: usize) {
+    update(app_handle, |inputs| inputs.pending_permissions = count);
+}
+
+pub fn set_unread(app_handle: &AppHandle, count: usize) {
+    update(app_handle, |inputs| inputs.unread = count);
+}
+
+pub fn set_count(app_handle: &AppHandle, count: usize) {
+    update(app_handle, |inputs| inputs.count = count);
+}
+
+/// Track a session's status from the event stream
+pub fn session_status(
+    app_handle: &AppHandle,
+    instance_url: &str,
+    session_id: &str,
+    status: SessionStatus,
+) {
+    let key = (instance_url.to_string(), session_id.to_string());
+    update(app_handle, |inputs| match status {
+        SessionStatus::Busy | SessionStatus::Retry => {
+            inputs.failed.remove(&key);
+            inputs.busy.insert(key);
+        }
+        SessionStatus::Idle => {
+            inputs.busy.remove(&key);
+        }
+    });
+}
+
+pub fn session_error(app_handle: &AppHandle, instance_url: &str, session_id: &str) {
+    let key = (instance_url.to_string(), session_id.to_string());
+    update(app_handle, |inputs| {
+        inputs.busy.remove(&key);
+        inputs.failed.insert(key);
+    });
+}
+
+pub fn set_animated(app_handle: &AppHandle, animated: bool) {
+    update(app_handle, |inputs| inputs.animated = animated);
+}
+
+/// The user opened the panel, so errors have been seen
+pub fn clear_errors(app_handle: &AppHandle) {
+    update(app_handle, |inputs| inputs.failed.clear());
+}
+
+/// Whether the event stream last reported the session as working
+pub fn is_busy(instance_url: &str, session_id: &str) -> bool {
+    INPUTS
+        .lock()
+        .map(|inputs| {
+            inputs
+                .busy
+                .contains(&(instance_url.to_string(), session_id.to_string()))
+        })
+        .unwrap_or(false)
+}
+
+/// Drop the sessions of an instance that went away
+pub fn forget_instance(app_handle: &AppHandle, instance_url: &str) {
+    update(app_handle, |inputs| {
+        inputs.busy.retain(|(url, _)| url != instance_url);
+        inputs.failed.retain(|(url, _)| url != instance_url);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle() -> Inputs {
+        Inputs {
+            theme: TrayTheme::Dark,
+            busy: HashSet::new(),
+            failed: HashSet::new(),
+            pending_permissions: 0,
+            unread: 0,
+            count: 0,
+            animated: true,
+            frame: 5,
+        }
+    }
+
+    fn session(id: &str) -> (String, String) {
+        ("http://127.0.0.1:4096".to_string(), id.to_string())
+    }
+
+    #[test]
+    fn badge_prefers_permissions_then_unread_then_count() {
+        let mut inputs = Inputs { count: 2, ..idle() };
+        assert_eq!(inputs.state().badge, 2);
+        inputs.unread = 3;
+        assert_eq!(inputs.state().badge, 3);
+        inputs.pending_permissions = 1;
+        assert_eq!(inputs.state().badge, 1);
+        inputs.pending_permissions = 25;
+        assert_eq!(inputs.state().badge, 10);
+    }
+
+    #[test]
+    fn status_prefers_permission_then_error_then_busy() {
+        let mut inputs = idle();
+        assert_eq!(inputs.state().status, TrayStatus::Idle);
+        inputs.busy.insert(session("a"));
+        assert_eq!(inputs.state().status, TrayStatus::Busy);
+        inputs.failed.insert(session("b"));
+        assert_eq!(inputs.state().status, TrayStatus::Error);
+        inputs.pending_permissions = 1;
+        assert_eq!(inputs.state().status, TrayStatus::AwaitingPermission);
+    }
+
+    #[test]
+    fn only_the_busy_dot_animates() {
+        let mut inputs = idle();
+        assert_eq!(inputs.state().frame, 0);
+        inputs.busy.insert(session("a"));
+        assert_eq!(inputs.state().frame, 5);
+        inputs.animated = false;
+        assert_eq!(inputs.state().frame, 0);
+        inputs.animated = true;
+        inputs.failed.insert(session("b"));
+        assert_eq!(inputs.state().frame, 0);
+    }
+
+    #[test]
+    fn plain_only_when_idle_without_badge() {
+        assert!(idle().state().is_plain());
+        assert!(!Inputs { count: 1, ..idle() }.state().is_plain());
+    }
+}
@@ -0,0 +1,375 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use opentray_client::{Client, PermissionReply, Session};
+use tauri::menu::{
+    Menu, MenuBuilder, MenuItemBuilder, PredefinedMenuItem, Submenu, SubmenuBuilder,
+};
+use tauri::{AppHandle, Emitter, Wry};
+
+use crate::discovery::OpenCodeInstance;
+use crate::permissions::PermissionData;
+
+// Sessions listed per instance
+const RECENT_SESSIONS: usize = 5;
+const TITLE_LENGTH: usize = 40;
+
+// Session events come in bursts, refetch once they settle
+const REFRESH_DELAY: Duration = Duration::from_millis(500);
+
+// Instances with their recent sessions, from the last refresh
+static SESSIONS: Mutex<Vec<(OpenCodeInstance, Vec<Session>)>> = Mutex::new(Vec::new());
+
+static REFRESH_PENDING: AtomicBool = AtomicBool::new(false);
+
+// Refreshes can overlap when a fetch is slow; each takes a generation when it
+// starts fetching and only a newer one than what `SESSIONS` holds is kept
+static REFRESH_GENERATION: AtomicU64 = AtomicU64::new(0);
+static SESSIONS_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Payload of `tray-open-session`: show the panel on this chat
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenSession {
+    pub instance_url: String,
+    pub directory: String,
+    pub session: Session,
+}
+
+/// Build the tray menu from the last known instances and sessions
+pub fn build(app_handle: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let show_item = MenuItemBuilder::with_id("show", "Show OpenTray").build(app_handle)?;
+    let new_session_item =
+        MenuItemBuilder::with_id("new_session", "New Session").build(app_handle)?;
+    let refresh_item = MenuItemBuilder::with_id("refresh", "Refresh").build(app_handle)?;
+    let settings_item = MenuItemBuilder::with_id("settings", "Settings...").build(app_handle)?;
+    let quit_item = MenuItemBuilder::with_id("quit", "Quit OpenTray").build(app_handle)?;
+
+    let mut menu = MenuBuilder::new(app_handle)
+        .item(&show_item)
+        .item(&new_session_item);
+
+    let pending = crate::permissions::list();
+    if !pending.is_empty() {
+        let header = MenuItemBuilder::new("Pending approvals")
+            .enabled(false)
+            .build(app_handle)?;
+        menu = menu
+            .item(&PredefinedMenuItem::separator(app_handle)?)
+            .item(&header);
+        for data in &pending {
+            menu = menu.item(&permission_submenu(app_handle, data)?);
+        }
+    }
+
+    let instances = SESSIONS.lock().map(|s| s.clone()).unwrap_or_default();
+    if !instances.is_empty() {
+        menu = menu.item(&PredefinedMenuItem::separator(app_handle)?);
+        for (instance, sessions) in &instances {
+            menu = menu.item(&instance_submenu(app_handle, instance, sessions)?);
+        }
+    }
+
+    menu.item(&PredefinedMenuItem::separator(app_handle)?)
+        .item(&refresh_item)
+        .item(&settings_item)
+        .item(&PredefinedMenuItem::separator(app_handle)?)
+        .item(&quit_item)
+        .build()
+}
+
+/// What is being asked and for which session, with the three possible answers
+fn permission_submenu(
+    app_handle: &AppHandle,
+    data: &PermissionData,
+) -> tauri::Result<Submenu<Wry>> {
+    let permission = data.request["permission"].as_str().unwrap_or("permission");
+    let label = match data.request["patterns"][0].as_str() {
+        Some(pattern) => format!("{}: {}", permission, pattern),
+        None => permission.to_string(),
+    };
+
+    let session = MenuItemBuilder::new(truncate(&data.session_title))
+        .enabled(false)
+        .build(app_handle)?;
+    let mut submenu = SubmenuBuilder::new(app_handle, truncate(&label))
+        .item(&session)
+        .separator();
+
+    for (text, reply) in [
+        ("Allow once", PermissionReply::Once),
+        ("Always allow", PermissionReply::Always),
+        ("Deny", PermissionReply::Reject),
+    ] {
+        let item =
+            MenuItemBuilder::with_id(reply_item_id(&data.id, reply), text).build(app_handle)?;
+        submenu = submenu.item(&item);
+    }
+
+    submenu.build()
+}
+
+/// Directory, recent sessions with a busy marker, then Abort items for the busy ones
+///
+/// Item IDs are `session:{instance_url}:{session_id}` and
+/// `abort:{instance_url}:{session_id}`, naming the session rather than a
+/// position, so a click that lands just after a rebuild still acts on what
+/// was shown.
+fn instance_submenu(
+    app_handle: &AppHandle,
+    instance: &OpenCodeInstance,
+    sessions: &[Session],
+) -> tauri::Result<Submenu<Wry>> {
+    let name = std::path::Path::new(&instance.directory)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| instance.url.clone());
+
+    let directory = MenuItemBuilder::new(&instance.directory)
+        .enabled(false)
+        .build(app_handle)?;
+    let mut submenu = SubmenuBuilder::new(app_handle, name)
+        .item(&directory)
+        .separator();
+
+    if sessions.is_empty() {
+        let empty = MenuItemBuilder::new("No sessions")
+            .enabled(false)
+            .build(app_handle)?;
+        submenu = submenu.item(&empty);
+    }
+
+    let mut busy = Vec::new();
+    for session in sessions {
+        let is_busy = crate::tray_icon::is_busy(&instance.url, &session.id);
+        let marker = if is_busy { "●" } else { "○" };
+
+        let id = session_item_id("session", &instance.url, &session.id);
+        let item = MenuItemBuilder::with_id(id, format!("{} {}", marker, title(session)))
+            .build(app_handle)?;
+        submenu = submenu.item(&item);
+
+        if is_busy {
+            busy.push(session);
+        }
+    }
+
+    if !busy.is_empty() {
+        submenu = submenu.separator();
+        for session in busy {
+            let id = session_item_id("abort", &instance.url, &session.id);
+            let item =
+                MenuItemBuilder::with_id(id, format!("Abort \u{201c}{}\u{201d}", title(session)))
+                    .build(app_handle)?;
+            submenu = submenu.item(&item);
+        }
+    }
+
+    submenu.build()
+}
+
+fn title(session: &Session) -> String {
+    match session.title.trim() {
+        "" => "Untitled".to_string(),
+        title => truncate(title),
+    }
+}
+
+/// Shorten text to fit a menu item
+fn truncate(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= TITLE_LENGTH {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(TITLE_LENGTH - 1).collect();
+    format!("{}\u{2026}", cut.trim_end())
+}
+
+/// Rebuild the menu from what we already know, e.g. after the permission queue changed
+pub fn rebuild(app_handle: &AppHandle) {
+    let Some(tray) = app_handle.tray_by_id("tray") else {
+        return;
+    };
+    match build(app_handle) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                log::warn!("failed to set tray menu: {}", e);
+            }
+        }
+        Err(e) => log::warn!("failed to build tray menu: {}", e),
+    }
+}
+
+/// Refetch recent sessions for every instance and rebuild the menu
+///
+/// Calls made while a refresh is already scheduled are folded into it. Results
+/// of a refresh that finishes after a later one are dropped.
+pub fn refresh(app_handle: &AppHandle) {
+    if REFRESH_PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(REFRESH_DELAY).await;
+        REFRESH_PENDING.store(false, Ordering::SeqCst);
+        let generation = REFRESH_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let mut fetched = Vec::new();
+        for instance in crate::discovery::instances() {
+            let client = Client::new(&instance.url).directory(&instance.directory);
+            let mut sessions: Vec<Session> = client
+                .sessions()
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|s| s.archived != Some(true))
+                .collect();
+            sessions.sort_by_key(|s| std::cmp::Reverse(s.time.updated));
+            sessions.truncate(RECENT_SESSIONS);
+            fetched.push((instance, sessions));
+        }
+
+        {
+            let Ok(mut current) = SESSIONS.lock() else {
+                return;
+            };
+            // Only written under the lock, so check and store can't interleave
+            if SESSIONS_GENERATION.load(Ordering::SeqCst) > generation {
+                return;
+            }
+            SESSIONS_GENERATION.store(generation, Ordering::SeqCst);
+            *current = fetched;
+        }
+        rebuild(&app_handle);
+    });
+}
+
+/// Show the panel on a chat
+pub fn open_session(app_handle: &AppHandle, open: &OpenSession) {
+    let _ = app_handle.emit("tray-open-session", open);
+    crate::command::show_panel_internal(app_handle);
+}
+
+/// `permission:{request_id}:{once|always|reject}`
+fn reply_item_id(request_id: &str, reply: PermissionReply) -> String {
+    let reply = match reply {
+        PermissionReply::Once => "once",
+        PermissionReply::Always => "always",
+        PermissionReply::Reject => "reject",
+    };
+    format!("permission:{}:{}", request_id, reply)
+}
+
+/// `permission:{request_id}:{once|always|reject}` split into its parts
+fn parse_reply(id: &str) -> Option<(&str, PermissionReply)> {
+    let (request_id, reply) = id.strip_prefix("permission:")?.rsplit_once(':')?;
+    let reply = match reply {
+        "once" => PermissionReply::Once,
+        "always" => PermissionReply::Always,
+        "reject" => PermissionReply::Reject,
+        _ => return None,
+    };
+    Some((request_id, reply))
+}
+
+/// `{kind}:{instance_url}:{session_id}`, where `kind` is `session` or `abort`
+fn session_item_id(kind: &str, instance_url: &str, session_id: &str) -> String {
+    format!("{}:{}:{}", kind, instance_url, session_id)
+}
+
+/// A `kind` item ID split into instance URL and session ID
+///
+/// Session IDs never contain `:`, so the URL is everything before the last one.
+fn parse_session_item<'a>(id: &'a str, kind: &str) -> Option<(&'a str, &'a str)> {
+    let rest = id.strip_prefix(kind)?.strip_prefix(':')?;
+    rest.rsplit_once(':')
+        .filter(|(url, session_id)| !url.is_empty() && !session_id.is_empty())
+}
+
+/// The listed session a `kind` item points at
+fn find_session(id: &str, kind: &str) -> Option<OpenSession> {
+    let (instance_url, session_id) = parse_session_item(id, kind)?;
+    let instances = SESSIONS.lock().ok()?;
+    let (instance, sessions) = instances.iter().find(|(i, _)| i.url == instance_url)?;
+    let session = sessions.iter().find(|s| s.id == session_id)?;
+    Some(OpenSession {
+        instance_url: instance.url.clone(),
+        directory: instance.directory.clone(),
+        session: session.clone(),
+    })
+}
+
+/// Handle a click on a per-session or permission item, other IDs are ignored
+pub fn handle(app_handle: &AppHandle, id: &str) {
+    if let Some((request_id, answer)) = parse_reply(id) {
+        crate::command::reply_queued(app_handle, request_id, answer);
+        return;
+    }
+
+    if let Some(open) = find_session(id, "session") {
+        open_session(app_handle, &open);
+    } else if let Some(target) = find_session(id, "abort") {
+        tauri::async_runtime::spawn(async move {
+            let client = Client::new(&target.instance_url).directory(&target.directory);
+            if let Err(e) = client.abort(&target.session.id).await {
+                log::warn!("failed to abort session {}: {}", target.session.id, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "http://127.0.0.1:4096";
+
+    #[test]
+    fn reply_items_round_trip() {
+        for reply in [
+            PermissionReply::Once,
+            PermissionReply::Always,
+            PermissionReply::Reject,
+        ] {
+            let id = reply_item_id("per_abc123", reply);
+            assert_eq!(parse_reply(&id), Some(("per_abc123", reply)));
+        }
+        assert_eq!(
+            reply_item_id("per_1", PermissionReply::Once),
+            "permission:per_1:once"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_replies_and_prefixes() {
+        assert_eq!(parse_reply("permission:per_1:maybe"), None);
+        assert_eq!(parse_reply("permission:per_1"), None);
+        assert_eq!(parse_reply("permissions:per_1:once"), None);
+        assert_eq!(parse_reply("session:per_1:once"), None);
+        assert_eq!(parse_reply("quit"), None);
+    }
+
+    #[test]
+    fn session_items_round_trip_urls_with_ports() {
+        for kind in ["session", "abort"] {
+            let id = session_item_id(kind, URL, "ses_x");
+            assert_eq!(id, format!("{}:http://127.0.0.1:4096:ses_x", kind));
+            assert_eq!(parse_session_item(&id, kind), Some((URL, "ses_x")));
+        }
+    }
+
+    #[test]
+    fn session_items_need_their_own_prefix() {
+        let id = session_item_id("session", URL, "ses_x");
+        assert_eq!(parse_session_item(&id, "abort"), None);
+        assert_eq!(
+            parse_session_item("sessions:http://a:1:ses_x", "session"),
+            None
+        );
+        assert_eq!(parse_session_item("session:ses_x", "session"), None);
+        assert_eq!(parse_session_item("session:http://a:1:", "session"), None);
+        assert_eq!(parse_session_item("refresh", "session"), None);
+    }
+}
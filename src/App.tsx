@@ -13,9 +13,11 @@ import {
   TodoList,
   ArchivedSessions,
   UpdateBanner,
+  NotificationInbox,
 } from "./components";
 import { useOpenCode, useTheme, useUpdater, useSettings } from "./hooks";
-import type { View } from "./lib/types";
+import type { View, TrayOpenSession, InboxEntry } from "./lib/types";
+import { fetchSessions } from "./lib/api";
 import { getDirectoryName } from "./lib/utils";
 
 
@@ -25,6 +27,7 @@ function App() {
   const [osType, setOsType] = useState<string>("");
   const [showTodoList, setShowTodoList] = useState(false);
   const [selectionMode, setSelectionMode] = useState(false);
+  const [unreadCount, setUnreadCount] = useState(0);
   const { theme, setTheme } = useTheme();
   const {
     updateAvailable,
@@ -87,6 +90,20 @@ function App() {
     }
   }, [initialized]);
 
+  // Unread notifications for the header badge
+  useEffect(() => {
+    invoke<InboxEntry[]>("list_notifications", { unreadOnly: true })
+      .then((entries) => setUnreadCount(entries.length))
+      .catch(() => {});
+
+    const unlisten = listen<number>("notifications-changed", (event) => {
+      setUnreadCount(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   // Auto-navigate when instance is selected
   useEffect(() => {
     if (selectedInstance && view === "instances") {
@@ -114,12 +131,36 @@ function App() {
       setView("settings");
     });
 
+    // A session picked from the tray menu
+    const unlistenOpenSession = listen<TrayOpenSession>("tray-open-session", (event) => {
+      const { instanceUrl, directory, session } = event.payload;
+      // The backend may know an instance the webview hasn't discovered yet
+      const instance = instances.find((i) => i.url === instanceUrl) ?? {
+        url: instanceUrl,
+        directory,
+        port: Number(new URL(instanceUrl).port),
+        connected: true,
+      };
+      if (selectedInstance?.url !== instance.url) {
+        selectInstance(instance);
+      }
+      selectSession(session);
+      setView("chat");
+    });
+
+    // Global shortcut bound to "abort_session"
+    const unlistenAbort = listen("shortcut-abort-session", () => {
+      abort();
+    });
+
     return () => {
       unlistenNewSession.then((fn) => fn());
       unlistenRefresh.then((fn) => fn());
       unlistenSettings.then((fn) => fn());
+      unlistenOpenSession.then((fn) => fn());
+      unlistenAbort.then((fn) => fn());
     };
-  }, [selectedInstance, refresh, createNewSession, selectSession]);
+  }, [instances, selectedInstance, refresh, createNewSession, selectInstance, selectSession, abort]);
 
   const handleInstanceSelect = (instance: typeof instances[0]) => {
     selectInstance(instance);
@@ -148,11 +189,24 @@ function App() {
       setView("instances");
     } else if (view === "archived") {
       setView("sessions");
-    } else if (view === "settings") {
+    } else if (view === "settings" || view === "inbox") {
       setView("instances");
     }
   };
 
+  // Open the chat a notification was about, if its instance is still around
+  const handleOpenNotification = async (entry: InboxEntry) => {
+    const instance = instances.find((i) => i.url === entry.instanceUrl);
+    if (!instance || !entry.sessionId) return;
+    const session = (await fetchSessions(instance)).find((s) => s.id === entry.sessionId);
+    if (!session) return;
+    if (selectedInstance?.url !== instance.url) {
+      selectInstance(instance);
+    }
+    selectSession(session);
+    setView("chat");
+  };
+
   const handleUnarchive = async (session: typeof sessions[0]) => {
     await unarchiveSession(session);
   };
@@ -178,6 +232,7 @@ function App() {
       case "diffs": return "Changes";
       case "archived": return "Archived Sessions";
       case "settings": return "Settings";
+      case "inbox": return "Notifications";
     }
   };
 
@@ -207,6 +262,8 @@ function App() {
         archivedCount={archivedSessions.length}
         showSelect={view === "sessions" || view === "archived"}
         showNewSession={view === "sessions"}
+        showInbox={view === "instances"}
+        unreadCount={unreadCount}
         onBack={handleBack}
         onRefresh={refresh}
         onSettings={() => setView("settings")}
@@ -216,6 +273,7 @@ function App() {
         onArchived={() => setView("archived")}
         onSelect={toggleSelectionMode}
         onNewSession={handleNewSession}
+        onInbox={() => setView("inbox")}
       />
 
       <main className="content">
@@ -269,6 +327,10 @@ function App() {
           />
         )}
 
+        {view === "inbox" && (
+          <NotificationInbox onOpen={handleOpenNotification} />
+        )}
+
         {view === "archived" && (
           <ArchivedSessions
             sessions={archivedSessions}
@@ -12,6 +12,8 @@ interface HeaderProps {
   archivedCount?: number;
   showSelect?: boolean;
   showNewSession?: boolean;
+  showInbox?: boolean;
+  unreadCount?: number;
   onBack: () => void;
   onRefresh: () => void;
   onSettings: () => void;
@@ -21,6 +23,7 @@ interface HeaderProps {
   onArchived?: () => void;
   onSelect?: () => void;
   onNewSession?: () => void;
+  onInbox?: () => void;
 }
 
 export function Header({
@@ -37,6 +40,8 @@ export function Header({
   archivedCount = 0,
   showSelect = false,
   showNewSession = false,
+  showInbox = false,
+  unreadCount = 0,
   onBack,
   onRefresh,
   onSettings,
@@ -46,6 +51,7 @@ export function Header({
   onArchived,
   onSelect,
   onNewSession,
+  onInbox,
 }: HeaderProps) {
   return (
     <header className="header">
@@ -90,6 +96,15 @@ export function Header({
             </svg>
           </button>
         )}
+        {showInbox && (
+          <button type="button" className="header-btn" onClick={onInbox} aria-label={`Notifications, ${unreadCount} unread`} data-tooltip="Notifications">
+            <svg width="16" height="16" viewBox="0 0 16 16" fill="none" aria-hidden="true">
+              <path d="M4 11V7a4 4 0 018 0v4l1 1.5H3L4 11z" stroke="currentColor" strokeWidth="1.5" strokeLinejoin="round"/>
+              <path d="M6.5 14h3" stroke="currentColor" strokeWidth="1.5" strokeLinecap="round"/>
+            </svg>
+            {unreadCount > 0 && <span className="badge">{unreadCount}</span>}
+          </button>
+        )}
         {showSettings && (
           <button type="button" className="header-btn" onClick={onSettings} aria-label="Settings" data-tooltip="Settings">
             <svg width="16" height="16" viewBox="0 0 16 16" fill="none" aria-hidden="true">
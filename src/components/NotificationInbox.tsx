@@ -0,0 +1,84 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type { InboxEntry } from "../lib/types";
+import { formatTime } from "../lib/utils";
+
+interface NotificationInboxProps {
+  onOpen: (entry: InboxEntry) => void;
+}
+
+export function NotificationInbox({ onOpen }: NotificationInboxProps) {
+  const [entries, setEntries] = useState<InboxEntry[]>([]);
+
+  // Reload whenever the backend records or marks a notification
+  useEffect(() => {
+    const load = () => {
+      invoke<InboxEntry[]>("list_notifications")
+        .then(setEntries)
+        .catch(() => {});
+    };
+    load();
+
+    const unlisten = listen<number>("notifications-changed", load);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const handleOpen = (entry: InboxEntry) => {
+    if (!entry.read) {
+      invoke("mark_read", { ids: [entry.id] }).catch(() => {});
+    }
+    onOpen(entry);
+  };
+
+  if (entries.length === 0) {
+    return (
+      <div className="empty">
+        <p>No notifications</p>
+      </div>
+    );
+  }
+
+  const unread = entries.filter((e) => !e.read).length;
+
+  return (
+    <div className="list fade-in">
+      <div className="selection-header">
+        <span className="selection-count">{unread > 0 ? `${unread} unread` : "All read"}</span>
+        <button
+          type="button"
+          className="selection-btn"
+          onClick={() => invoke("mark_read", { ids: null }).catch(() => {})}
+          disabled={unread === 0}
+        >
+          Mark All Read
+        </button>
+        <div className="selection-divider" />
+        <button
+          type="button"
+          className="selection-btn danger"
+          onClick={() => invoke("clear_notifications").catch(() => {})}
+        >
+          Clear
+        </button>
+      </div>
+
+      {entries.map((entry) => (
+        <button
+          type="button"
+          key={entry.id}
+          className={`list-item ${entry.read ? "" : "selected"}`}
+          onClick={() => handleOpen(entry)}
+        >
+          <div className="list-item-content">
+            <span className="list-item-title">{entry.title}</span>
+            <span className="list-item-subtitle">{entry.body}</span>
+          </div>
+          <span className="list-item-meta">{formatTime(entry.timestamp)}</span>
+        </button>
+      ))}
+    </div>
+  );
+}
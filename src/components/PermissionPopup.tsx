@@ -4,26 +4,49 @@ import { listen } from "@tauri-apps/api/event";
 import type { PermissionRequest } from "../lib/types";
 
 interface PermissionData {
+  id: string;
   request: PermissionRequest;
   sessionTitle: string;
   instanceUrl: string;
 }
 
+// Error returned by `permission_reply`
+interface PermissionReplyError {
+  kind: "instanceGone" | "unreachable" | "rejected";
+  message: string;
+}
+
 export function PermissionPopup() {
   const [data, setData] = useState<PermissionData | null>(null);
   const [responding, setResponding] = useState(false);
+  const [pendingCount, setPendingCount] = useState(0);
+  const [error, setError] = useState<string | null>(null);
 
   useEffect(() => {
     // Listen for permission request data from main window
     const unlisten = listen<PermissionData>("permission-request", (event) => {
       setData(event.payload);
+      setError(null);
+    });
+
+    // Track how many requests are queued behind this one
+    const unlistenCount = listen<number>("permissions-changed", (event) => {
+      setPendingCount(event.payload);
     });
 
     // Request initial data
-    invoke("get_pending_permission").catch(() => {});
+    invoke<PermissionData | null>("get_pending_permission")
+      .then((pending) => {
+        if (pending) setData(pending);
+      })
+      .catch(() => {});
+    invoke<PermissionData[]>("list_pending_permissions")
+      .then((pending) => setPendingCount(pending.length))
+      .catch(() => {});
 
     return () => {
       unlisten.then((fn) => fn());
+      unlistenCount.then((fn) => fn());
     };
   }, []);
 
@@ -31,19 +54,22 @@ export function PermissionPopup() {
     if (!data || responding) return;
     
     setResponding(true);
+    setError(null);
     try {
-      // Emit the reply back
+      // The backend posts the reply and shows the next request, or hides the popup
       await invoke("permission_reply", {
         instanceUrl: data.instanceUrl,
-        sessionId: data.request.sessionID,
         requestId: data.request.id,
         reply,
       });
-      
-      // Close the popup window
-      await invoke("hide_permission_popup");
-    } catch (error) {
-      console.error("Failed to reply to permission:", error);
+    } catch (err) {
+      // Unreachable and rejected replies keep the request queued, so it stays shown
+      const replyError = err as PermissionReplyError;
+      if (replyError?.kind === "unreachable") {
+        setError(`Couldn't reach OpenCode, try again: ${replyError.message}`);
+      } else {
+        setError(replyError?.message ?? String(err));
+      }
     } finally {
       setResponding(false);
     }
@@ -101,6 +127,9 @@ export function PermissionPopup() {
         <div className="permission-popup-title">
           <h3>Permission Required</h3>
           <span className="permission-popup-session">{data.sessionTitle}</span>
+          {pendingCount > 1 && (
+            <span className="permission-popup-more">{pendingCount - 1} more waiting</span>
+          )}
         </div>
       </div>
 
@@ -118,6 +147,8 @@ export function PermissionPopup() {
         </div>
       </div>
 
+      {error && <div className="permission-popup-error">{error}</div>}
+
       <div className="permission-popup-actions">
         <button
           type="button"
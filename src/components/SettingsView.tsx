@@ -1,10 +1,47 @@
 import { useState, useMemo, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { open } from "@tauri-apps/plugin-dialog";
 import type { Theme, OpenCodeConfig, MCPServer, OpenCodeInstance, FullProvider } from "../lib/types";
 import { useSettings } from "../hooks";
+import type { CategorySound, NotificationEvent, NotificationSettings, SoundCategory } from "../hooks";
+import { formatCommandError } from "../lib/utils";
 
 type SettingsTab = "general" | "app" | "providers" | "mcp" | "config";
 
+// Actions that can be bound to a global shortcut (ShortcutAction in src-tauri/src/shortcuts.rs)
+const SHORTCUT_ACTIONS: { id: string; label: string }[] = [
+  { id: "toggle_panel", label: "Toggle OpenTray" },
+  { id: "new_session", label: "New session" },
+  { id: "abort_session", label: "Abort current session" },
+  { id: "approve_permission", label: "Approve permission" },
+  { id: "deny_permission", label: "Deny permission" },
+  { id: "open_settings", label: "Open settings" },
+];
+
+// Events the backend notifies about (NotificationEvent in src-tauri/src/notifications.rs)
+const NOTIFICATION_EVENTS: { id: NotificationEvent; label: string }[] = [
+  { id: "session_done", label: "Session Finished" },
+  { id: "session_error", label: "Session Failed" },
+  { id: "permission_required", label: "Permission Required" },
+  { id: "todos_completed", label: "Todo List Completed" },
+];
+
+// Sound categories the backend plays (SoundCategory in src-tauri/src/sound.rs)
+const SOUND_CATEGORIES: { id: SoundCategory; label: string }[] = [
+  { id: "done", label: "Done Sound" },
+  { id: "error", label: "Error Sound" },
+  { id: "permission", label: "Permission Sound" },
+];
+
+// Registration result reported by the backend for each binding
+interface BindingStatus {
+  action: string;
+  shortcut: string;
+  registered: boolean;
+  error?: { kind: string; message: string } | null;
+}
+
 // MCP config types from opencode.json
 interface LocalMCPConfig {
   type?: "remote" | "local";
@@ -57,9 +94,71 @@ export function SettingsView({
   const { settings: appSettings, updateSettings } = useSettings();
 
   // Shortcut editing state
-  const [isEditingShortcut, setIsEditingShortcut] = useState(false);
+  const [editingAction, setEditingAction] = useState<string | null>(null);
   const [shortcutKeys, setShortcutKeys] = useState<string[]>([]);
+  const [shortcutStatus, setShortcutStatus] = useState<BindingStatus[]>([]);
+  const [captureError, setCaptureError] = useState<{ action: string; message: string } | null>(null);
   const shortcutInputRef = useRef<HTMLButtonElement>(null);
+
+  const [soundError, setSoundError] = useState<{ category: SoundCategory; message: string } | null>(null);
+
+  const quietHours = appSettings.notifications.quiet_hours;
+
+  const updateSound = (category: SoundCategory, updates: Partial<CategorySound>) => {
+    setSoundError(null);
+    updateSettings({
+      sounds: { ...appSettings.sounds, [category]: { ...appSettings.sounds[category], ...updates } },
+    });
+  };
+
+  const chooseSound = async (category: SoundCategory) => {
+    const file = await open({ filters: [{ name: "Sound", extensions: ["wav", "ogg"] }] });
+    if (typeof file === "string") {
+      updateSound(category, { file });
+    }
+  };
+
+  const previewSound = async (category: SoundCategory) => {
+    setSoundError(null);
+    try {
+      await invoke("preview_sound", { category });
+    } catch (error) {
+      setSoundError({ category, message: formatCommandError(error) });
+    }
+  };
+
+  const updateNotifications = (updates: Partial<NotificationSettings>) => {
+    updateSettings({ notifications: { ...appSettings.notifications, ...updates } });
+  };
+
+  // Per-event toggles are rules without a directory; directory rules are edited in settings.json
+  const isEventEnabled = (event: NotificationEvent) =>
+    appSettings.notifications.rules.find((r) => r.event === event && !r.directory)?.enabled ?? true;
+
+  const setEventEnabled = (event: NotificationEvent, enabled: boolean) => {
+    const rules = appSettings.notifications.rules;
+    const exists = rules.some((r) => r.event === event && !r.directory);
+    updateNotifications({
+      rules: exists
+        ? rules.map((r) => (r.event === event && !r.directory ? { ...r, enabled } : r))
+        : [...rules, { event, enabled }],
+    });
+  };
+
+  // Per-binding registration errors
+  useEffect(() => {
+    invoke<BindingStatus[]>("get_shortcut_status")
+      .then(setShortcutStatus)
+      .catch(() => {});
+
+    const unlisten = listen<BindingStatus[]>("shortcuts-changed", (event) => {
+      setShortcutStatus(event.payload);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
   
   // Config editor state
   const [configExists, setConfigExists] = useState<boolean | null>(null);
@@ -73,6 +172,15 @@ export function SettingsView({
 
   const configPath = instance?.directory ? `${instance.directory}/opencode.json` : null;
 
+  const updateLocalMcps = (parsed: LocalConfigFile) => {
+    setLocalMcps(
+      Object.entries(parsed.mcp ?? {}).map(([name, cfg]) => ({
+        name,
+        config: cfg,
+      }))
+    );
+  };
+
   // Load config file when instance changes
   useEffect(() => {
     if (!configPath) return;
@@ -86,18 +194,10 @@ export function SettingsView({
           const content = await invoke<string>("read_file", { path: configPath });
           setConfigContent(content);
           
-          // Parse MCPs from config
+          // Parse MCPs from config (comments and trailing commas are allowed)
           try {
-            const parsed: LocalConfigFile = JSON.parse(content);
-            if (parsed.mcp) {
-              const mcps = Object.entries(parsed.mcp).map(([name, cfg]) => ({
-                name,
-                config: cfg
-              }));
-              setLocalMcps(mcps);
-            } else {
-              setLocalMcps([]);
-            }
+            const parsed = await invoke<LocalConfigFile>("read_opencode_config", { path: configPath });
+            updateLocalMcps(parsed);
           } catch {
             setLocalMcps([]);
           }
@@ -108,7 +208,7 @@ export function SettingsView({
         setConfigError(null);
         setConfigDirty(false);
       } catch (err) {
-        setConfigError(String(err));
+        setConfigError(formatCommandError(err));
       }
     };
     
@@ -119,51 +219,74 @@ export function SettingsView({
   const handleSaveConfig = async () => {
     if (!configPath) return;
     
-    // Validate JSON
-    let parsed: LocalConfigFile;
-    try {
-      parsed = JSON.parse(configContent);
-    } catch {
-      setConfigError("Invalid JSON syntax");
-      return;
-    }
-    
     setConfigSaving(true);
     setConfigError(null);
     
     try {
-      await invoke("write_file", { path: configPath, content: configContent });
+      // Checked against the config schema before it is written
+      await invoke("save_opencode_config", { path: configPath, content: configContent });
       setConfigExists(true);
       setConfigDirty(false);
       
       // Update local MCPs after save
-      if (parsed.mcp) {
-        const mcps = Object.entries(parsed.mcp).map(([name, cfg]) => ({
-          name,
-          config: cfg
-        }));
-        setLocalMcps(mcps);
-      } else {
-        setLocalMcps([]);
-      }
+      const parsed = await invoke<LocalConfigFile>("read_opencode_config", { path: configPath });
+      updateLocalMcps(parsed);
     } catch (err) {
-      setConfigError(String(err));
+      setConfigError(formatCommandError(err));
     } finally {
       setConfigSaving(false);
     }
   };
+
+  // Enable or disable a local MCP server, leaving the rest of the file as written
+  const handleToggleMcp = async (name: string, enabled: boolean) => {
+    if (!configPath) return;
+
+    try {
+      const content = await invoke<string>("edit_opencode_config", {
+        path: configPath,
+        edits: [{ path: ["mcp", name, "enabled"], value: enabled }],
+      });
+      if (!configDirty) {
+        setConfigContent(content);
+      }
+      setLocalMcps((mcps) =>
+        mcps.map((mcp) => (mcp.name === name ? { ...mcp, config: { ...mcp.config, enabled } } : mcp))
+      );
+      setConfigError(null);
+    } catch (err) {
+      setConfigError(formatCommandError(err));
+    }
+  };
+
+  // Roll opencode.json back to the version before the last save
+  const handleRestoreConfig = async () => {
+    if (!configPath) return;
+
+    try {
+      await invoke("restore_backup", { path: configPath });
+      const content = await invoke<string>("read_file", { path: configPath });
+      setConfigContent(content);
+      setConfigDirty(false);
+      setConfigError(null);
+    } catch (err) {
+      setConfigError(formatCommandError(err));
+    }
+  };
   
   // Combine API MCPs with local MCPs
   const combinedMcps = useMemo(() => {
-    const result: { name: string; status: string; type: string; url?: string; command?: string; tools?: string[] }[] = [];
+    const result: { name: string; status: string; type: string; url?: string; command?: string; tools?: string[]; enabled?: boolean }[] = [];
     
     // First add MCPs from OpenCode API (they have real status)
     for (const server of mcpServers) {
+      const local = localMcps.find(l => l.name === server.name);
       result.push({
         name: server.name,
         status: server.status,
         type: "running",
         tools: server.tools,
+        enabled: local ? local.config.enabled !== false : undefined,
       });
     }
     
@@ -177,6 +300,7 @@ export function SettingsView({
           type: local.config.type || (local.config.command ? "local" : "remote"),
           url: local.config.url,
           command: local.config.command,
+          enabled: local.config.enabled !== false,
         });
       }
     }
@@ -223,8 +347,31 @@ export function SettingsView({
     }
   };
 
+  const stopEditingShortcut = () => {
+    setEditingAction(null);
+    setShortcutKeys([]);
+  };
+
+  // Saving re-registers the global shortcuts (no restart required)
+  const saveShortcut = (action: string, shortcut: string) => {
+    updateSettings({ shortcuts: { ...appSettings.shortcuts, [action]: shortcut } });
+  };
+
+  // Validate before saving so bad accelerators never reach settings.json
+  const captureShortcut = async (action: string, shortcut: string) => {
+    try {
+      const canonical = await invoke<string>("validate_shortcut", { shortcut });
+      setCaptureError(null);
+      saveShortcut(action, canonical);
+      stopEditingShortcut();
+    } catch (err) {
+      setCaptureError({ action, message: formatCommandError(err) });
+    }
+  };
+
   // Handle shortcut key capture
   const handleShortcutKeyDown = (e: React.KeyboardEvent) => {
+    if (!editingAction) return;
     e.preventDefault();
     e.stopPropagation();
 
@@ -234,23 +381,21 @@ export function SettingsView({
     // Detect platform
     const isMac = (window as unknown as { __OPENTRAY__?: { platform?: string } }).__OPENTRAY__?.platform === "macos";
 
-    if (e.metaKey && isMac) modifiers.push("Cmd");
-    if (e.ctrlKey && !isMac) modifiers.push("Ctrl");
-    if (e.ctrlKey && isMac) modifiers.push("Ctrl");
+    if (e.metaKey) modifiers.push(isMac ? "Cmd" : "Super");
+    if (e.ctrlKey) modifiers.push("Ctrl");
     if (e.altKey) modifiers.push("Alt");
     if (e.shiftKey) modifiers.push("Shift");
 
-    // Map key names
-    let keyName = key;
-    if (key === " ") keyName = "Space";
-    else if (key.length === 1) keyName = key.toUpperCase();
-    else if (key === "ArrowUp") keyName = "Up";
-    else if (key === "ArrowDown") keyName = "Down";
-    else if (key === "ArrowLeft") keyName = "Left";
-    else if (key === "ArrowRight") keyName = "Right";
-    else if (key === "Escape") {
-      setIsEditingShortcut(false);
-      setShortcutKeys([]);
+    if (key === "Escape" && modifiers.length === 0) {
+      stopEditingShortcut();
+      return;
+    }
+
+    // Backspace without modifiers unbinds the action
+    if ((key === "Backspace" || key === "Delete") && modifiers.length === 0) {
+      setCaptureError(null);
+      saveShortcut(editingAction, "");
+      stopEditingShortcut();
       return;
     }
 
@@ -260,30 +405,17 @@ export function SettingsView({
       return;
     }
 
-    // Need at least one modifier + a key
-    if (modifiers.length > 0) {
-      const newShortcut = [...modifiers, keyName].join("+");
-      setShortcutKeys([...modifiers, keyName]);
-
-      // Save the shortcut and update it in real-time
-      updateSettings({ global_shortcut: newShortcut });
-
-      // Update the global shortcut immediately (no restart required)
-      invoke("update_global_shortcut", { shortcutStr: newShortcut })
-        .catch(err => console.error("Failed to update shortcut:", err));
-
-      setIsEditingShortcut(false);
-      setShortcutKeys([]);
-    }
-  };
+    // Use the physical key so Shift+/ stays "Slash" rather than "?"
+    const keyName = e.code
+      .replace(/^Key|^Digit|^Arrow/, "")
+      .replace(/^Numpad/, "Num");
 
-  const handleShortcutBlur = () => {
-    setIsEditingShortcut(false);
-    setShortcutKeys([]);
+    captureShortcut(editingAction, [...modifiers, keyName].join("+"));
   };
 
-  const startEditingShortcut = () => {
-    setIsEditingShortcut(true);
+  const startEditingShortcut = (action: string) => {
+    setCaptureError(null);
+    setEditingAction(action);
     setShortcutKeys([]);
     setTimeout(() => shortcutInputRef.current?.focus(), 0);
   };
@@ -439,6 +571,114 @@ export function SettingsView({
                   </label>
                 </div>
               </div>
+              {appSettings.sound_enabled &&
+                SOUND_CATEGORIES.map(({ id, label }) => {
+                  const sound = appSettings.sounds[id];
+                  return (
+                    <div key={id} className="settings-item">
+                      <span className="settings-label">{label}</span>
+                      <div className="settings-control">
+                        <input
+                          type="range"
+                          min={0}
+                          max={1}
+                          step={0.05}
+                          value={sound.volume}
+                          onChange={(e) => updateSound(id, { volume: Number(e.target.value) })}
+                          aria-label={`${label} volume`}
+                        />
+                        <button
+                          type="button"
+                          className="settings-shortcut-input"
+                          onClick={() => chooseSound(id)}
+                          onContextMenu={(e) => {
+                            e.preventDefault();
+                            updateSound(id, { file: null });
+                          }}
+                          title={sound.file ? `${sound.file} (right-click for the default)` : "Choose a WAV or OGG file"}
+                        >
+                          {sound.file ? sound.file.split(/[\\/]/).pop() : "Default"}
+                        </button>
+                        <button type="button" className="config-btn secondary" onClick={() => previewSound(id)}>
+                          Play
+                        </button>
+                      </div>
+                      {soundError?.category === id && <span className="settings-error">{soundError.message}</span>}
+                    </div>
+                  );
+                })}
+              <div className="settings-item">
+                <span className="settings-label">Desktop Notifications</span>
+                <div className="settings-control">
+                  <label className="toggle">
+                    <input
+                      type="checkbox"
+                      checked={appSettings.notifications.enabled}
+                      onChange={(e) => updateNotifications({ enabled: e.target.checked })}
+                    />
+                    <span className="toggle-slider" />
+                  </label>
+                </div>
+              </div>
+              {appSettings.notifications.enabled && (
+                <>
+                  {NOTIFICATION_EVENTS.map((event) => (
+                    <div key={event.id} className="settings-item">
+                      <span className="settings-label">{event.label}</span>
+                      <div className="settings-control">
+                        <label className="toggle">
+                          <input
+                            type="checkbox"
+                            checked={isEventEnabled(event.id)}
+                            onChange={(e) => setEventEnabled(event.id, e.target.checked)}
+                          />
+                          <span className="toggle-slider" />
+                        </label>
+                      </div>
+                    </div>
+                  ))}
+                  <div className="settings-item">
+                    <span className="settings-label">Quiet Hours</span>
+                    <div className="settings-control">
+                      <label className="toggle">
+                        <input
+                          type="checkbox"
+                          checked={!!quietHours}
+                          onChange={(e) =>
+                            updateNotifications({
+                              quiet_hours: e.target.checked ? { start: "22:00", end: "07:00" } : null,
+                            })
+                          }
+                        />
+                        <span className="toggle-slider" />
+                      </label>
+                    </div>
+                  </div>
+                  {quietHours && (
+                    <div className="settings-item">
+                      <span className="settings-label">From / Until</span>
+                      <div className="settings-control">
+                        <input
+                          type="time"
+                          className="settings-select"
+                          value={quietHours.start}
+                          onChange={(e) =>
+                            updateNotifications({ quiet_hours: { ...quietHours, start: e.target.value } })
+                          }
+                        />
+                        <input
+                          type="time"
+                          className="settings-select"
+                          value={quietHours.end}
+                          onChange={(e) =>
+                            updateNotifications({ quiet_hours: { ...quietHours, end: e.target.value } })
+                          }
+                        />
+                      </div>
+                    </div>
+                  )}
+                </>
+              )}
             </div>
 
             <div className="settings-section">
@@ -456,34 +696,57 @@ export function SettingsView({
                   </label>
                 </div>
               </div>
+              <div className="settings-item">
+                <span className="settings-label">Animate Tray Icon</span>
+                <div className="settings-control">
+                  <label className="toggle">
+                    <input
+                      type="checkbox"
+                      checked={appSettings.animate_tray_icon}
+                      onChange={(e) => updateSettings({ animate_tray_icon: e.target.checked })}
+                    />
+                    <span className="toggle-slider" />
+                  </label>
+                </div>
+              </div>
             </div>
 
             <div className="settings-section">
-              <h3 className="settings-section-title">Keyboard Shortcut</h3>
-              <div className="settings-item">
-                <span className="settings-label">Toggle OpenTray</span>
-                {isEditingShortcut ? (
-                  <button
-                    ref={shortcutInputRef}
-                    type="button"
-                    className="settings-shortcut-input editing"
-                    onKeyDown={handleShortcutKeyDown}
-                    onBlur={handleShortcutBlur}
-                  >
-                    {shortcutKeys.length > 0 ? shortcutKeys.join("+") : "Press keys..."}
-                  </button>
-                ) : (
-                  <button
-                    type="button"
-                    className="settings-shortcut-input"
-                    onClick={startEditingShortcut}
-                  >
-                    {appSettings.global_shortcut}
-                  </button>
-                )}
-              </div>
+              <h3 className="settings-section-title">Keyboard Shortcuts</h3>
+              {SHORTCUT_ACTIONS.map(({ id, label }) => {
+                const error =
+                  captureError?.action === id
+                    ? captureError
+                    : shortcutStatus.find((s) => s.action === id)?.error;
+                return (
+                  <div key={id} className="settings-item">
+                    <span className="settings-label">{label}</span>
+                    {editingAction === id ? (
+                      <button
+                        ref={shortcutInputRef}
+                        type="button"
+                        className="settings-shortcut-input editing"
+                        onKeyDown={handleShortcutKeyDown}
+                        onBlur={stopEditingShortcut}
+                      >
+                        {shortcutKeys.length > 0 ? shortcutKeys.join("+") : "Press keys..."}
+                      </button>
+                    ) : (
+                      <button
+                        type="button"
+                        className="settings-shortcut-input"
+                        onClick={() => startEditingShortcut(id)}
+                        title={error ? error.message : undefined}
+                      >
+                        {appSettings.shortcuts?.[id] || "Not set"}
+                      </button>
+                    )}
+                    {error && <span className="settings-error">{error.message}</span>}
+                  </div>
+                );
+              })}
               <p className="settings-hint">
-                Click to change. Press Escape to cancel.
+                Click to change. Press Escape to cancel, Backspace to clear.
               </p>
             </div>
           </>
@@ -588,6 +851,16 @@ export function SettingsView({
                       </span>
                       <span className="mcp-name">{server.name}</span>
                       <span className="mcp-status-text">{server.status}</span>
+                      {server.enabled !== undefined && (
+                        <label className="toggle" title={server.enabled ? "Disable in opencode.json" : "Enable in opencode.json"}>
+                          <input
+                            type="checkbox"
+                            checked={server.enabled}
+                            onChange={(e) => handleToggleMcp(server.name, e.target.checked)}
+                          />
+                          <span className="toggle-slider" />
+                        </label>
+                      )}
                     </div>
                     <div className="mcp-details">
                       {server.type && (
@@ -694,6 +967,16 @@ export function SettingsView({
                   >
                     Reset
                   </button>
+                  {configExists && (
+                    <button
+                      type="button"
+                      className="config-btn secondary"
+                      onClick={handleRestoreConfig}
+                      disabled={configSaving}
+                    >
+                      Undo Last Save
+                    </button>
+                  )}
                   <button
                     type="button"
                     className="config-btn primary"
@@ -12,3 +12,4 @@ export { AnimatedStatus } from "./AnimatedStatus";
 export { TodoList } from "./TodoList";
 export { ArchivedSessions } from "./ArchivedSessions";
 export { UpdateBanner } from "./UpdateBanner";
+export { NotificationInbox } from "./NotificationInbox";
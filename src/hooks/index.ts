@@ -2,4 +2,4 @@ export { useOpenCode } from "./useOpenCode";
 export { useTheme } from "./useTheme";
 export { useUpdater } from "./useUpdater";
 export { useSettings } from "./useSettings";
-export type { AppSettings } from "./useSettings";
+export type { AppSettings, CategorySound, NotificationEvent, NotificationSettings, PermissionRule, SettingsChange, SoundCategory } from "./useSettings";
@@ -84,9 +84,6 @@ export function useOpenCode(): UseOpenCodeReturn {
   const updateMessagePartRef = useRef<(messageId: string, part: Part) => void>(() => {});
   const addMessageRef = useRef<(sessionId: string, messageId: string, info: MessageWithParts["info"]) => void>(() => {});
   
-  // Track previous status for notification logic
-  const prevStatusRef = useRef<SessionStatus>("idle");
-
   // Load archived sessions from localStorage
   useEffect(() => {
     const stored = localStorage.getItem("archivedSessions");
@@ -521,41 +518,10 @@ export function useOpenCode(): UseOpenCodeReturn {
     } catch {}
   }, []);
 
-  // Update tray icon based on status
+  // Badge the tray with the number of changed files; busy and error overlays come from the backend
   useEffect(() => {
-    const updateBadge = async () => {
-      let badge: string | null = null;
-      
-      if (sessionStatus === "busy") {
-        badge = "•";
-      } else if (diffs.length > 0) {
-        badge = diffs.length.toString();
-      }
-      
-      try {
-        await invoke("update_tray_icon", { badge });
-      } catch {}
-    };
-    
-    updateBadge();
-  }, [sessionStatus, diffs]);
-
-  // Send notification when session completes (transitions from busy to idle)
-  useEffect(() => {
-    // Only notify when transitioning FROM busy TO idle with diffs
-    if (
-      prevStatusRef.current === "busy" && 
-      sessionStatus === "idle" && 
-      selectedSession && 
-      diffs.length > 0
-    ) {
-      invoke("send_notification", {
-        title: "Task Completed",
-        body: `${selectedSession.title} has ${diffs.length} file${diffs.length > 1 ? "s" : ""} changed`,
-      }).catch(() => {});
-    }
-    prevStatusRef.current = sessionStatus;
-  }, [sessionStatus, diffs, selectedSession]);
+    invoke("update_tray_icon", { update: { badge: diffs.length || null } }).catch(() => {});
+  }, [diffs]);
 
   // Show permission popup when a permission request comes in
   useEffect(() => {
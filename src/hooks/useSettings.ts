@@ -1,20 +1,89 @@
 import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+// Auto-approval rule evaluated by the backend (src-tauri/src/policy.rs)
+export interface PermissionRule {
+  tool?: string;
+  pattern?: string;
+  path?: string;
+  directory?: string;
+  action: "allow" | "deny" | "ask";
+}
+
+export type NotificationEvent =
+  | "session_done"
+  | "session_error"
+  | "permission_required"
+  | "todos_completed";
+
+// Turns notifications on or off, first matching rule wins (src-tauri/src/notifications.rs)
+export interface NotificationRule {
+  event?: NotificationEvent;
+  directory?: string;
+  enabled: boolean;
+}
+
+export interface NotificationSettings {
+  enabled: boolean;
+  rules: NotificationRule[];
+  // Local "HH:MM" range without notifications, may wrap past midnight
+  quiet_hours?: { start: string; end: string } | null;
+}
+
+export type SoundCategory = "done" | "error" | "permission";
+
+// Sound for one category (src-tauri/src/sound.rs); no file means the bundled sound
+export interface CategorySound {
+  file?: string | null;
+  volume: number;
+}
 
 export interface AppSettings {
+  // Settings file format version, managed by the backend
+  version?: number;
   autostart: boolean;
   sound_enabled: boolean;
   compact_mode: boolean;
-  global_shortcut: string;
+  // Pulse the tray icon while a session is busy
+  animate_tray_icon: boolean;
+  // Accelerator per action, e.g. { toggle_panel: "Ctrl+Shift+O" }; empty means unbound
+  shortcuts: Record<string, string>;
+  discovery_port_start?: number;
+  discovery_port_count?: number;
+  permission_rules?: PermissionRule[];
+  notifications: NotificationSettings;
+  sounds: Record<SoundCategory, CategorySound>;
+}
+
+// One entry of the `settings-changed` event's diff
+export interface SettingsChange {
+  key: string;
+  old: unknown;
+  new: unknown;
+}
+
+interface SettingsChangedEvent {
+  changes: SettingsChange[];
+  settings: AppSettings;
 }
 
 const defaultSettings: AppSettings = {
   autostart: false,
   sound_enabled: true,
   compact_mode: false,
-  global_shortcut: typeof window !== "undefined" && (window as unknown as { __OPENTRAY__?: { platform?: string } }).__OPENTRAY__?.platform === "macos" 
-    ? "Cmd+Shift+O" 
-    : "Ctrl+Shift+O",
+  animate_tray_icon: true,
+  notifications: { enabled: true, rules: [] },
+  sounds: {
+    done: { volume: 0.8 },
+    error: { volume: 0.8 },
+    permission: { volume: 0.8 },
+  },
+  shortcuts: {
+    toggle_panel: typeof window !== "undefined" && (window as unknown as { __OPENTRAY__?: { platform?: string } }).__OPENTRAY__?.platform === "macos" 
+      ? "Cmd+Shift+O" 
+      : "Ctrl+Shift+O",
+  },
 };
 
 export function useSettings() {
@@ -33,6 +102,15 @@ export function useSettings() {
       }
     };
     loadSettings();
+
+    // Saves from any window (or the backend) arrive here
+    const unlisten = listen<SettingsChangedEvent>("settings-changed", (event) => {
+      setSettings({ ...defaultSettings, ...event.payload.settings });
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   const updateSettings = useCallback(async (updates: Partial<AppSettings>) => {
@@ -1,100 +1,12 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import type { OpenCodeInstance, Session, SessionState, FileDiff, MessageWithParts, Part, OpenCodeConfig, MCPServer, FullProvider, TodoItem } from "./types";
 
-const DEFAULT_PORT = 4096;
-const MAX_PORT_SCAN = 10;
-
-// Health response from OpenCode
-interface HealthResponse {
-  healthy: boolean;
-  version: string;
-}
-
-// Path response from OpenCode
-interface PathResponse {
-  home: string;
-  state: string;
-  config: string;
-  worktree: string;
-  directory: string;
-}
-
-// Project response from OpenCode
-interface ProjectResponse {
-  id: string;
-  path: string;
-  name?: string;
-  icon?: string;
-  color?: string;
-}
-
-// Scan for OpenCode instances on local ports
+// Instances discovered by the Rust backend (src-tauri/src/discovery.rs)
+// The backend keeps scanning while the webview is hidden and emits
+// "instances-changed" whenever the set of instances changes
 export async function discoverInstances(): Promise<OpenCodeInstance[]> {
-  const instances: OpenCodeInstance[] = [];
-  
-  // Scan ports in parallel for faster discovery
-  const portChecks = Array.from({ length: MAX_PORT_SCAN }, (_, i) => DEFAULT_PORT + i);
-  
-  const results = await Promise.allSettled(
-    portChecks.map(async (port) => {
-      const url = `http://127.0.0.1:${port}`;
-      
-      // Check health first
-      const healthRes = await fetch(`${url}/global/health`, {
-        method: "GET",
-        signal: AbortSignal.timeout(1000),
-      });
-      
-      if (!healthRes.ok) return null;
-      
-      const health: HealthResponse = await healthRes.json();
-      if (!health.healthy) return null;
-      
-      // Get directory from /path endpoint
-      let directory = "Unknown";
-      try {
-        const pathRes = await fetch(`${url}/path`, {
-          method: "GET",
-          signal: AbortSignal.timeout(1000),
-        });
-        
-        if (pathRes.ok) {
-          const pathData: PathResponse = await pathRes.json();
-          directory = pathData.directory;
-        }
-      } catch {
-        // Try /project/current as fallback
-        try {
-          const projectRes = await fetch(`${url}/project/current`, {
-            method: "GET",
-            signal: AbortSignal.timeout(1000),
-          });
-          
-          if (projectRes.ok) {
-            const project: ProjectResponse = await projectRes.json();
-            directory = project.path;
-          }
-        } catch {
-          // Keep "Unknown"
-        }
-      }
-      
-      return {
-        url,
-        port,
-        directory,
-        connected: true,
-        version: health.version,
-      } as OpenCodeInstance;
-    })
-  );
-  
-  for (const result of results) {
-    if (result.status === "fulfilled" && result.value) {
-      instances.push(result.value);
-    }
-  }
-  
-  return instances;
+  return invoke<OpenCodeInstance[]>("list_instances");
 }
 
 // Fetch sessions from an OpenCode instance
@@ -268,13 +180,23 @@ export interface SSECallbacks {
   onStatusChanged?: (sessionId: string, status: "idle" | "busy" | "retry") => void;
 }
 
-// Subscribe to SSE events with debounced session updates
+// Payload of "opencode-event", emitted by the backend's stream of each
+// instance (src-tauri/src/events.rs)
+interface InstanceEvent {
+  instanceUrl: string;
+  directory: string | null;
+  event: { type: string; properties?: any };
+}
+
+// "busy" or { type: "busy", ... } depending on the OpenCode version
+type StatusValue = "idle" | "busy" | "retry" | { type: "idle" | "busy" | "retry" };
+
+// Subscribe to an instance's events with debounced session updates
+// The backend holds the only SSE connection and forwards what it receives
 export function subscribeToEvents(
   instance: OpenCodeInstance,
   callbacks: SSECallbacks
 ): () => void {
-  const eventSource = new EventSource(`${instance.url}/global/event`);
-  
   // Debounce session updates to batch rapid changes
   let sessionUpdateTimeout: ReturnType<typeof setTimeout> | null = null;
   let pendingSessionUpdates = new Map<string, Session>();
@@ -297,12 +219,13 @@ export function subscribeToEvents(
     sessionUpdateTimeout = setTimeout(flushSessionUpdates, 100);
   };
   
-  eventSource.onmessage = (event) => {
+  const unlisten = listen<InstanceEvent>("opencode-event", (message) => {
+    if (message.payload.instanceUrl !== instance.url) {
+      return;
+    }
     try {
-      const data = JSON.parse(event.data);
-      const payload = data.payload || data;
-      const type = payload.type;
-      const properties = payload.properties || {};
+      const { type } = message.payload.event;
+      const properties = message.payload.event.properties || {};
       
       switch (type) {
         case "message.updated":
@@ -343,32 +266,33 @@ export function subscribeToEvents(
           callbacks.onPermissionReplied?.(properties.sessionID, properties.id);
           break;
           
-        case "session.status":
-          callbacks.onStatusChanged?.(properties.sessionID, properties.status);
+        case "session.status": {
+          const status: StatusValue = properties.status;
+          callbacks.onStatusChanged?.(
+            properties.sessionID,
+            typeof status === "string" ? status : status.type
+          );
           break;
+        }
           
         case "server.connected":
-          console.log("Connected to OpenCode SSE");
+          console.log("Connected to OpenCode event stream");
           break;
           
         case "server.heartbeat":
           break;
       }
     } catch (error) {
-      console.error("Error parsing SSE event:", error);
+      console.error("Error handling OpenCode event:", error);
     }
-  };
-  
-  eventSource.onerror = (error) => {
-    console.error("SSE error:", error);
-  };
+  });
   
   return () => {
     if (sessionUpdateTimeout) {
       clearTimeout(sessionUpdateTimeout);
     }
     flushSessionUpdates(); // Flush any pending updates before closing
-    eventSource.close();
+    unlisten.then((fn) => fn());
   };
 }
 
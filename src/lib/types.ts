@@ -271,6 +271,25 @@ export interface OpenCodeInstance {
   version?: string;
 }
 
+// Payload of the `tray-open-session` event, mirrors `OpenSession` in src-tauri/src/tray_menu.rs
+export interface TrayOpenSession {
+  instanceUrl: string;
+  directory: string;
+  session: Session;
+}
+
+// A notification the backend sent, from `list_notifications` (src-tauri/src/inbox.rs)
+export interface InboxEntry {
+  id: number;
+  timestamp: number;
+  kind: "session_done" | "session_error" | "permission_required" | "todos_completed";
+  title: string;
+  body: string;
+  instanceUrl: string;
+  sessionId?: string | null;
+  read: boolean;
+}
+
 export type SessionStatus = "idle" | "busy" | "retry";
 
 export interface SessionState {
@@ -280,7 +299,7 @@ export interface SessionState {
   permissionRequest?: PermissionRequest;
 }
 
-export type View = "instances" | "sessions" | "chat" | "diffs" | "settings" | "archived";
+export type View = "instances" | "sessions" | "chat" | "diffs" | "settings" | "archived" | "inbox";
 
 export type Theme = "system" | "light" | "dark";
 
@@ -25,6 +25,14 @@ export function getDirectoryName(path: string): string {
   return path.split("/").pop() || path;
 }
 
+// Backend commands return either a string or a structured `{ kind, message }` error
+export function formatCommandError(error: unknown): string {
+  if (error && typeof error === "object" && "message" in error) {
+    return String((error as { message: unknown }).message);
+  }
+  return String(error);
+}
+
 export function cn(...classes: (string | boolean | undefined)[]): string {
   return classes.filter(Boolean).join(" ");
 }